@@ -0,0 +1,92 @@
+//! The last update of each view on disk, shown right away on the next start
+//! until the first live update arrives.
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Where the last update of a view for one set of params is kept.
+pub struct File {
+    /// `None` without a cache directory or in a replay
+    path: Option<PathBuf>,
+    /// the update of [`File::keep`], written once the file is dropped
+    pending: Option<String>,
+}
+
+impl File {
+    pub fn new<P: Serialize>(kind: &str, params: &P) -> Self {
+        if crate::session::is_replay() {
+            return File {
+                path: None,
+                pending: None,
+            };
+        }
+        let params = serde_json::to_string(params).expect("params are serializable");
+        let path = dir().map(|d| d.join(format!("{}-{:016x}.json", kind, fnv1a(&params))));
+        File {
+            path,
+            pending: None,
+        }
+    }
+
+    /// The stored update; a missing or outdated file has none.
+    pub fn load<T: DeserializeOwned>(&self) -> Option<T> {
+        let content = fs::read_to_string(self.path.as_ref()?).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Replaces the stored update, failures are ignored as the cache is only
+    /// a convenience.
+    pub fn store<T: Serialize>(&self, update: &T) {
+        if let Ok(content) = serde_json::to_string(update) {
+            self.write(content);
+        }
+    }
+
+    /// Stores `update` once the view is closed instead of right away, views
+    /// receive a new update every poll.
+    pub fn keep<T: Serialize>(&mut self, update: &T) {
+        if self.path.is_some() {
+            self.pending = serde_json::to_string(update).ok();
+        }
+    }
+
+    fn write(&self, content: String) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            _ = fs::create_dir_all(dir);
+        }
+        // written next to the file first so a crash never leaves half of it
+        let tmp = path.with_extension("tmp");
+        if fs::write(&tmp, content).is_ok() {
+            _ = fs::rename(&tmp, path);
+        }
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        if let Some(content) = self.pending.take() {
+            self.write(content);
+        }
+    }
+}
+
+/// `$XDG_CACHE_HOME/gmon` or `~/.cache/gmon`.
+fn dir() -> Option<PathBuf> {
+    let cache = env::var_os("XDG_CACHE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))?;
+    Some(cache.join("gmon"))
+}
+
+/// A hash which stays the same across builds, unlike the one of the std.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,92 @@
+use std::ffi::OsStr;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::Args;
+
+use crate::fetchers::artifacts::{ArtifactSource, DownloadError};
+use crate::fetchers::projects::FetchError;
+use crate::gitlab_ref::*;
+
+#[derive(Debug, Args)]
+pub struct ArtifactsArgs {
+    /// The artifacts are taken from the latest successful pipeline of the ref,
+    /// `-` for the ref watched last
+    gitlab_ref: GitlabRef,
+    /// Name of the job which created the artifacts
+    #[arg(long)]
+    job: String,
+    /// Only download this file (path within the artifact archive)
+    #[arg(long)]
+    file: Option<String>,
+    /// Directory the artifacts are written to
+    #[arg(long, default_value = ".")]
+    out: PathBuf,
+}
+
+pub async fn run(gapi: gitlab::AsyncGitlab, args: &ArtifactsArgs) {
+    let gitlab_ref = match crate::recent::watch(&args.gitlab_ref) {
+        Ok(gitlab_ref) => gitlab_ref,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+    let GitlabRef::Branch(project, git_ref) = &gitlab_ref else {
+        eprintln!("artifacts require a ref like path/to/project@main");
+        process::exit(2);
+    };
+
+    let source = ArtifactSource::Ref {
+        git_ref: git_ref.clone(),
+        job: args.job.clone(),
+    };
+    let path = match destination(&args.out, "artifacts.zip", args.file.as_deref()) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("artifacts can not be written to {}: {}", args.out.display(), err);
+            process::exit(2);
+        }
+    };
+    match crate::fetchers::artifacts::download_artifacts(
+        &gapi,
+        project,
+        &source,
+        args.file.as_deref(),
+        &path,
+    )
+    .await
+    {
+        Ok(()) => println!("{}", path.display()),
+        Err(DownloadError::Write(err)) => {
+            eprintln!("writing artifacts to {} failed due to: {}", path.display(), err);
+            process::exit(1);
+        }
+        Err(err) => {
+            let found = crate::fetchers::projects::find_project(&gapi, project).await;
+            if let Err(missing @ FetchError::NotFound(_)) = found {
+                eprintln!("{}", missing);
+                process::exit(2);
+            }
+            eprintln!("downloading artifacts failed due to: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Where downloaded artifacts go within `dir`: a single `file` of the archive
+/// keeps its file name, the archive itself is called `archive_name`.
+pub(crate) fn destination(
+    dir: &Path,
+    archive_name: &str,
+    file: Option<&str>,
+) -> io::Result<PathBuf> {
+    let name = match file {
+        Some(f) => Path::new(f)
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file"))?,
+        None => OsStr::new(archive_name),
+    };
+    Ok(dir.join(name))
+}
@@ -0,0 +1,155 @@
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
+
+use clap::{Args, Subcommand};
+
+use crate::config::Config;
+use crate::keymap::Keymap;
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    #[clap(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigCommand {
+    /// Ask for host and token, check them against GitLab and write the config
+    Init {
+        /// Replace an existing config
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the config with the token and webhook secret redacted
+    Show,
+    /// Check the config, including host and token against GitLab
+    Validate {
+        /// Skip contacting GitLab
+        #[arg(long)]
+        offline: bool,
+    },
+}
+
+pub async fn run(path: &Path, args: &ConfigArgs) {
+    match &args.command {
+        ConfigCommand::Init { force } => init(path, *force).await,
+        ConfigCommand::Show => show(path),
+        ConfigCommand::Validate { offline } => validate(path, *offline).await,
+    }
+}
+
+async fn init(path: &Path, force: bool) {
+    if path.exists() && !force {
+        eprintln!("{} exists already, replace it with --force", path.display());
+        process::exit(2);
+    }
+
+    let mut stdin = io::stdin().lock();
+    let (host, token) = loop {
+        let host = prompt(&mut stdin, "GitLab host (like gitlab.com, without https://): ");
+        let token = prompt(&mut stdin, "Personal access token (scope read_api or api): ");
+        match crate::gitlab_api(&host, &token).await {
+            Ok(_) => break (host, token),
+            Err(err) => eprintln!("connecting to {} failed due to: {}\n", host, err),
+        }
+    };
+
+    let content = format!(
+        "host = {}\ntoken = {}\n",
+        toml::Value::String(host),
+        toml::Value::String(token)
+    );
+    if let Err(err) = write_private(path, &content) {
+        eprintln!("writing {} failed due to: {}", path.display(), err);
+        process::exit(1);
+    }
+    println!("wrote {}", path.display());
+}
+
+/// Reads one trimmed line, quits on the end of the input.
+fn prompt(stdin: &mut impl BufRead, question: &str) -> String {
+    loop {
+        print!("{}", question);
+        io::stdout().flush().expect("flush stdout");
+        let mut line = String::new();
+        match stdin.read_line(&mut line) {
+            Ok(0) | Err(_) => process::exit(2),
+            Ok(_) if line.trim().is_empty() => continue,
+            Ok(_) => return line.trim().to_string(),
+        }
+    }
+}
+
+/// Writes the file readable by the user only as it contains the token.
+fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+fn show(path: &Path) {
+    let content = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(err) => {
+            eprintln!("failed reading config {} due to: {}", path.display(), err);
+            process::exit(2);
+        }
+    };
+    let mut table: toml::Table = match toml::from_str(&content) {
+        Ok(t) => t,
+        Err(err) => {
+            eprintln!("failed loading config {} due to: {}", path.display(), err.message());
+            process::exit(2);
+        }
+    };
+    for secret in ["token", "webhook_secret"] {
+        if let Some(value) = table.get_mut(secret) {
+            *value = toml::Value::String("<redacted>".to_string());
+        }
+    }
+
+    println!("# {}", path.display());
+    print!("{}", toml::to_string(&table).expect("config is serializable"));
+}
+
+async fn validate(path: &Path, offline: bool) {
+    let problems = check(path, offline).await;
+    if problems.is_empty() {
+        println!("{} is valid", path.display());
+        return;
+    }
+    for problem in problems {
+        eprintln!("{}", problem);
+    }
+    process::exit(1);
+}
+
+async fn check(path: &Path, offline: bool) -> Vec<String> {
+    let config = match Config::load(path) {
+        Ok(c) => c,
+        Err(err) => return vec![err],
+    };
+
+    let mut problems = Vec::new();
+    if let Err(err) = Keymap::new(&config.keys) {
+        problems.push(format!("invalid keys: {}", err));
+    }
+    for (name, dashboard) in &config.dashboards {
+        if dashboard.refs.is_empty() {
+            problems.push(format!("dashboard {} has no refs", name));
+        }
+    }
+    if !offline {
+        if let Err(err) = crate::gitlab_api(&config.host, &config.token).await {
+            problems.push(format!("connecting to {} failed due to: {}", config.host, err));
+        }
+    }
+    problems
+}
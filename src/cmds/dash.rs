@@ -0,0 +1,430 @@
+use ratatui::prelude::*;
+use ratatui::Frame;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::cmds::Failure;
+use crate::config::{
+    Dashboard, DashboardEntry, DashboardLayout, DashboardsFile, PROJECT_DASHBOARDS,
+};
+use crate::events::*;
+use crate::fetchers::pipelines::{BranchPipelineUpdate, PipelinesQueryArgs};
+use crate::fetchers::poll::Controller;
+use crate::fetchers::projects::{FetchError, NotFound};
+use crate::fetchers::JobQueryParams;
+use crate::gitlab_ref::*;
+use crate::humanize;
+use crate::theme;
+use crate::theme::Theme;
+
+#[derive(Debug, Args)]
+pub struct DashArgs {
+    /// Name of the dashboard
+    name: String,
+    /// Read the dashboard from this file instead of .gmon.toml and the config
+    #[arg(long)]
+    file: Option<PathBuf>,
+    /// Stop fetching jobs of a pipeline after this many
+    #[arg(long, default_value_t = crate::fetchers::JOB_LIMIT)]
+    max_jobs: usize,
+}
+
+/// One pipeline strip of the dashboard.
+struct Panel {
+    entry: DashboardEntry,
+    project: Option<BranchPipelineUpdate>,
+    /// the project does not exist or is not accessible
+    missing: Option<NotFound>,
+    /// the last request failed, the pipelines shown are the ones fetched before
+    error: Option<String>,
+    /// index into the pipelines of the project; 0 is the most recent one
+    selected: usize,
+}
+
+impl Panel {
+    fn new(entry: DashboardEntry) -> Self {
+        Panel {
+            entry,
+            project: None,
+            missing: None,
+            error: None,
+            selected: 0,
+        }
+    }
+
+    fn params(&self) -> PipelinesQueryArgs {
+        let mut params = match GitlabRef::from(self.entry.gitlab_ref.as_str()) {
+            GitlabRef::Repo(repo) => PipelinesQueryArgs::new(repo),
+            GitlabRef::Branch(repo, branch) => PipelinesQueryArgs::new(repo).with_reference(branch),
+        }
+        .with_count(self.entry.count);
+        if let Some(status) = &self.entry.status {
+            params = params.with_status(status.clone());
+        }
+        params
+    }
+
+    fn update(&mut self, update: Result<BranchPipelineUpdate, FetchError>) {
+        match update {
+            Ok(p) => {
+                self.selected = self.selected.min(p.pipelines.len().saturating_sub(1));
+                self.project = Some(p);
+                self.missing = None;
+                self.error = None;
+            }
+            Err(FetchError::NotFound(missing)) => self.missing = Some(missing),
+            Err(FetchError::Transient(err)) => self.error = Some(err),
+        }
+    }
+
+    fn height(&self) -> u16 {
+        match self.entry.durations {
+            true => 12,
+            false => 6,
+        }
+    }
+}
+
+struct App {
+    /// the pipelines of all panels at once, in the order of the panels
+    receiver: tokio::sync::mpsc::Receiver<Vec<Result<BranchPipelineUpdate, FetchError>>>,
+    controller: Controller,
+    /// keeps the last update for the next start
+    cache: crate::cache::File,
+    /// the panels show the cached pipelines, no update was received yet
+    stale: bool,
+    /// when the last update was fetched
+    updated: Option<chrono::DateTime<chrono::Utc>>,
+    panels: Vec<Panel>,
+    layout: DashboardLayout,
+    /// index of the panel the arrow keys act on
+    focused: usize,
+    /// the focused panel shows what the status symbols mean
+    legend: bool,
+    /// the first row of panels shown, the dashboard may be taller than the terminal
+    top: usize,
+    /// where the pipelines were drawn as (panel, pipeline), for the mouse
+    hits: Vec<(Rect, (usize, usize))>,
+}
+
+impl App {
+    fn new(gapi: &gitlab::AsyncGitlab, dashboard: Dashboard) -> Self {
+        let mut panels: Vec<Panel> = dashboard.refs.into_iter().map(Panel::new).collect();
+        let params: Vec<PipelinesQueryArgs> = panels.iter().map(Panel::params).collect();
+        let (controller, schedule) = crate::fetchers::poll::control();
+        let receiver = crate::session::updates("dash", &params, || {
+            crate::fetchers::pipelines::batched_pipelines(gapi.clone(), params.clone(), schedule)
+        });
+        let cache = crate::cache::File::new("dash", &params);
+        let cached: Option<Vec<Result<BranchPipelineUpdate, FetchError>>> = cache.load();
+        let stale = cached.is_some();
+        for (panel, update) in panels.iter_mut().zip(cached.into_iter().flatten()) {
+            panel.update(update);
+        }
+
+        App {
+            receiver,
+            controller,
+            cache,
+            stale,
+            updated: None,
+            panels,
+            layout: dashboard.layout,
+            focused: 0,
+            legend: false,
+            top: 0,
+            hits: Vec::new(),
+        }
+    }
+
+    fn update(&mut self) {
+        let Ok(updates) = self.receiver.try_recv() else {
+            return;
+        };
+        self.cache.keep(&updates);
+        self.stale = false;
+        let fetched_at = updates.iter().flatten().map(|u| u.fetched_at).min();
+        self.updated = fetched_at.or(self.updated);
+        for (panel, update) in self.panels.iter_mut().zip(updates) {
+            panel.update(update);
+        }
+    }
+
+    /// Number of panels next to each other.
+    fn columns(&self) -> usize {
+        match self.layout {
+            DashboardLayout::Rows => 1,
+            DashboardLayout::Grid => 2,
+        }
+    }
+
+    /// The height of each row of panels.
+    fn row_heights(&self) -> Vec<u16> {
+        self.panels
+            .chunks(self.columns())
+            .map(|row| row.iter().map(Panel::height).max().unwrap_or(0))
+            .collect()
+    }
+
+    /// The height of all panels, at most the one of the terminal.
+    fn height(&self) -> u16 {
+        let height = self.row_heights().iter().sum();
+        match ratatui::crossterm::terminal::size() {
+            Ok((_, rows)) => rows.min(height),
+            Err(_) => height,
+        }
+    }
+
+    /// Scrolls the row of the focused panel into the `height` shown.
+    fn scroll(&mut self, height: u16) {
+        let heights = self.row_heights();
+        let row = self.focused / self.columns();
+        self.top = self.top.min(row);
+        while self.top < row && heights[self.top..=row].iter().sum::<u16>() > height {
+            self.top += 1;
+        }
+    }
+
+    fn focus(&mut self, offset: isize) {
+        let focused = self.focused.saturating_add_signed(offset);
+        if focused < self.panels.len() {
+            self.focused = focused;
+        }
+    }
+
+    fn select(&mut self, offset: isize) {
+        let Some(panel) = self.panels.get_mut(self.focused) else {
+            return;
+        };
+        let count = panel.project.as_ref().map_or(0, |p| p.pipelines.len());
+        let selected = panel.selected.saturating_add_signed(offset);
+        if selected < count {
+            panel.selected = selected;
+        }
+    }
+
+    /// Params to open the selected pipeline of the focused panel with.
+    fn open_selected(&self) -> Option<JobQueryParams> {
+        let project = self.panels.get(self.focused)?.project.as_ref()?;
+        let selected = project.pipelines.get(self.panels[self.focused].selected)?;
+        Some(
+            JobQueryParams::new(project.project.clone())
+                .with_pipeline(selected.iid.clone()),
+        )
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let updated = match self.stale {
+            true => Some(humanize::STALE.to_string()),
+            false => self
+                .updated
+                .map(|u| humanize::updated(u, self.controller.paused())),
+        };
+        self.scroll(frame.area().height);
+        self.hits = render(
+            frame,
+            &self.panels,
+            self.columns(),
+            self.top,
+            self.focused,
+            self.legend,
+            updated.as_deref(),
+        );
+    }
+}
+
+pub async fn run(
+    gapi: gitlab::AsyncGitlab,
+    dashboards: HashMap<String, Dashboard>,
+    args: &DashArgs,
+) -> Result<(), Failure> {
+    let mut dashboard = find_dashboard(dashboards, args).map_err(Failure::usage)?;
+    // all resolved before any is recorded, recording changes what `-` means
+    for entry in &mut dashboard.refs {
+        let gitlab_ref = crate::recent::resolve(&GitlabRef::from(entry.gitlab_ref.as_str()))
+            .map_err(Failure::usage)?;
+        entry.gitlab_ref = gitlab_ref.to_string();
+    }
+    // the first panel ends up as the most recent ref
+    for entry in dashboard.refs.iter().rev() {
+        crate::recent::record(&GitlabRef::from(entry.gitlab_ref.as_str()));
+    }
+
+    let mut app = App::new(&gapi, dashboard);
+    let mut event_handler = EventHandler::new(250);
+
+    while let Some(params) = select(&mut app, &mut event_handler).await {
+        let params = params.with_job_limit(args.max_jobs);
+        crate::cmds::pipeline::show(Some(gapi.clone()), params, &mut event_handler).await;
+    }
+    Ok(())
+}
+
+/// The dashboard `args.name` of `--file`, of the [`PROJECT_DASHBOARDS`] of the
+/// current directory or of the config, in that order.
+fn find_dashboard(
+    mut dashboards: HashMap<String, Dashboard>,
+    args: &DashArgs,
+) -> Result<Dashboard, String> {
+    let file = args
+        .file
+        .clone()
+        .unwrap_or_else(|| Path::new(PROJECT_DASHBOARDS).to_path_buf());
+    let mut project = DashboardsFile::load(&file)?;
+
+    let dashboard = match &args.file {
+        Some(_) => project.dashboards.remove(&args.name),
+        None => project
+            .dashboards
+            .remove(&args.name)
+            .or_else(|| dashboards.remove(&args.name)),
+    };
+    match dashboard {
+        Some(d) if d.refs.is_empty() => Err(format!("dashboard {} has no refs", args.name)),
+        Some(d) => Ok(d),
+        None => Err(format!("there is no dashboard called {}", args.name)),
+    }
+}
+
+/// Shows the dashboard until the user either quits (`None`) or opens one of
+/// the pipelines.
+async fn select(app: &mut App, event_handler: &mut EventHandler) -> Option<JobQueryParams> {
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
+    let viewport = ratatui::Viewport::Inline(app.height());
+    let mut terminal =
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
+            .expect("terminal setup to work");
+
+    loop {
+        app.update();
+        terminal
+            .draw(|frame| app.render(frame))
+            .expect("failed to draw frame");
+
+        let columns = app.columns() as isize;
+        match event_handler.next().await {
+            Event::Tick => {}
+            Event::Quit => return None,
+            Event::Action(action) => match action {
+                Action::Quit => return None,
+                Action::Refresh => app.controller.refresh(),
+                Action::Pause => app.controller.toggle_pause(),
+                Action::Prev => app.select(-1),
+                Action::Next => app.select(1),
+                Action::Up => app.focus(-columns),
+                Action::Down => app.focus(columns),
+                Action::ToggleLegend => app.legend = !app.legend,
+                Action::Open => {
+                    if let Some(params) = app.open_selected() {
+                        terminal.clear().expect("failed to clear terminal");
+                        return Some(params);
+                    }
+                }
+                _ => {}
+            },
+            Event::Key(_) => {}
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let Some((panel, pipeline)) = hit(&app.hits, &mouse) else {
+                        continue;
+                    };
+                    app.focused = panel;
+                    app.panels[panel].selected = pipeline;
+                    if let Some(params) = app.open_selected() {
+                        terminal.clear().expect("failed to clear terminal");
+                        return Some(params);
+                    }
+                }
+                MouseEventKind::ScrollUp => app.select(-1),
+                MouseEventKind::ScrollDown => app.select(1),
+                _ => {}
+            },
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
+        }
+    }
+}
+
+/// Returns the areas of the pipelines of all panels shown, the rows of panels
+/// starting with `top`.
+fn render(
+    frame: &mut Frame,
+    panels: &[Panel],
+    columns: usize,
+    top: usize,
+    focused: usize,
+    legend: bool,
+    updated: Option<&str>,
+) -> Vec<(Rect, (usize, usize))> {
+    let mut hits = Vec::new();
+    let mut remaining = frame.area();
+    for (r, row) in panels.chunks(columns).enumerate().skip(top) {
+        if remaining.height == 0 {
+            break;
+        }
+        let height = row.iter().map(Panel::height).max().unwrap_or(0);
+        let [row_area, rest] =
+            Layout::vertical([Constraint::Length(height), Constraint::Fill(1)]).areas(remaining);
+        remaining = rest;
+
+        let areas =
+            Layout::horizontal((0..columns).map(|_| Constraint::Fill(1))).split(row_area);
+        for (c, (panel, area)) in row.iter().zip(areas.iter()).enumerate() {
+            let area = area.intersection(Rect {
+                height: panel.height(),
+                ..*area
+            });
+            let index = r * columns + c;
+            let is_focused = index == focused;
+            let panel_hits =
+                render_panel(frame, area, panel, is_focused, legend && is_focused, updated);
+            hits.extend(panel_hits.into_iter().map(|(a, p)| (a, (index, p))));
+        }
+    }
+    hits
+}
+
+/// Only the focused panel shows its selected pipeline.
+fn render_panel(
+    frame: &mut Frame,
+    area: Rect,
+    panel: &Panel,
+    focused: bool,
+    legend: bool,
+    updated: Option<&str>,
+) -> Vec<(Rect, usize)> {
+    if let Some(missing) = &panel.missing {
+        crate::cmds::pipelines::render_not_found(frame, area, missing);
+        return Vec::new();
+    }
+    let error = panel
+        .error
+        .as_ref()
+        .map(|err| Line::styled(err.clone(), theme::Catpuccin::red()));
+    let Some(project) = &panel.project else {
+        let mut loading = theme::Catpuccin.loading(panel.entry.gitlab_ref.clone());
+        if let Some(error) = error {
+            loading = loading.title_bottom(error);
+        }
+        frame.render_widget(loading, area);
+        return Vec::new();
+    };
+
+    let selected = match focused {
+        true => panel.selected,
+        false => usize::MAX,
+    };
+    crate::cmds::pipelines::render_in(
+        frame,
+        area,
+        project,
+        selected,
+        panel.entry.durations,
+        legend,
+        error,
+        updated,
+    )
+}
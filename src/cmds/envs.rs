@@ -0,0 +1,206 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Row, Table};
+use ratatui::Frame;
+use std::io;
+
+use clap::Args;
+
+use crate::events::*;
+use crate::fetchers::environments::{
+    DeploymentStatus, Environment, EnvironmentsQueryArgs, EnvironmentsUpdate,
+};
+use crate::fetchers::poll::Controller;
+use crate::fetchers::projects::{FetchError, NotFound};
+use crate::humanize;
+use crate::theme;
+use crate::theme::Theme;
+
+#[derive(Debug, Args)]
+pub struct EnvsArgs {
+    /// path/to/project
+    project: String,
+}
+
+struct App {
+    receiver: tokio::sync::mpsc::Receiver<Result<EnvironmentsUpdate, FetchError>>,
+    controller: Controller,
+    project: Option<EnvironmentsUpdate>,
+    /// the project does not exist or is not accessible
+    missing: Option<NotFound>,
+    /// the last request failed, the environments are fetched again on the next poll
+    error: Option<String>,
+    /// when the last update was fetched
+    updated: Option<chrono::DateTime<chrono::Utc>>,
+    /// shown until the first update arrives
+    title: String,
+}
+
+impl App {
+    fn new(gapi: gitlab::AsyncGitlab, args: &EnvsArgs) -> Self {
+        let (controller, schedule) = crate::fetchers::poll::control();
+        let receiver = crate::fetchers::environments::environments(
+            gapi,
+            EnvironmentsQueryArgs::new(args.project.clone()),
+            schedule,
+        );
+
+        App {
+            receiver,
+            controller,
+            project: None,
+            missing: None,
+            error: None,
+            updated: None,
+            title: args.project.clone(),
+        }
+    }
+
+    fn update(&mut self) {
+        // check if there is a new project update
+        match self.receiver.try_recv() {
+            Ok(Ok(p)) => {
+                self.updated = Some(p.fetched_at);
+                self.project = Some(p);
+                self.error = None;
+            }
+            Ok(Err(FetchError::NotFound(missing))) => self.missing = Some(missing),
+            Ok(Err(FetchError::Transient(err))) => self.error = Some(err),
+            Err(_) => {}
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        if let Some(missing) = &self.missing {
+            crate::cmds::pipelines::render_not_found(frame, frame.area(), missing);
+            return;
+        }
+        match &self.project {
+            Some(p) => {
+                let updated = self
+                    .updated
+                    .map(|u| humanize::updated(u, self.controller.paused()));
+                render(frame, p, updated.as_deref(), self.error.as_deref());
+            }
+            None => {
+                let mut loading = theme::Catpuccin.loading(self.title.clone());
+                if let Some(err) = &self.error {
+                    let err = Line::styled(err.clone(), theme::Catpuccin::red());
+                    loading = loading.title_bottom(err);
+                }
+                frame.render_widget(loading, frame.area());
+            }
+        }
+    }
+}
+
+pub async fn run(gapi: gitlab::AsyncGitlab, args: &EnvsArgs) {
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
+    let viewport = ratatui::Viewport::Inline(15);
+    let mut terminal =
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
+            .expect("terminal setup to work");
+
+    let mut app = App::new(gapi, args);
+    let mut event_handler = EventHandler::new(250);
+
+    loop {
+        app.update();
+        terminal
+            .draw(|frame| app.render(frame))
+            .expect("failed to draw frame");
+
+        match event_handler.next().await {
+            Event::Tick => {}
+            Event::Quit => break,
+            Event::Action(Action::Quit) => break,
+            Event::Action(Action::Refresh) => app.controller.refresh(),
+            Event::Action(Action::Pause) => app.controller.toggle_pause(),
+            Event::Action(_) | Event::Key(_) | Event::Mouse(_) => {}
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
+        }
+    }
+}
+
+fn status_style(status: &DeploymentStatus) -> Style {
+    match status {
+        DeploymentStatus::SUCCESS => theme::Catpuccin::green(),
+        DeploymentStatus::FAILED => theme::Catpuccin::red(),
+        DeploymentStatus::RUNNING => theme::Catpuccin::blue(),
+        _ => theme::Catpuccin::text().add_modifier(Modifier::DIM),
+    }
+}
+
+fn environment_row(env: &Environment) -> Row<'static> {
+    let tier = env
+        .tier
+        .as_ref()
+        .map(|t| format!("{:?}", t).to_lowercase())
+        .unwrap_or_default();
+    let pending = env.pending_jobs.join(", ");
+
+    let Some(d) = &env.deployment else {
+        return Row::new([
+            Line::from(env.name.clone()),
+            Line::from(tier),
+            Line::from("never deployed"),
+        ])
+        .style(theme::Catpuccin::text());
+    };
+
+    let deployed = match d.deployed_at {
+        Some(at) => {
+            let age = chrono::Utc::now().signed_duration_since(at);
+            format!("{} ago", humanize::age(age))
+        }
+        None => "-".to_string(),
+    };
+    Row::new([
+        Line::from(env.name.clone()),
+        Line::from(tier),
+        Line::styled(format!("{:?}", d.status).to_lowercase(), status_style(&d.status)),
+        Line::from(d.git_ref.clone()),
+        Line::from(d.sha.chars().take(8).collect::<String>()),
+        Line::from(d.deployer.clone()),
+        Line::from(deployed),
+        Line::styled(pending, theme::Catpuccin::blue()),
+    ])
+    .style(theme::Catpuccin::text())
+}
+
+fn render(
+    frame: &mut Frame,
+    project: &EnvironmentsUpdate,
+    updated: Option<&str>,
+    error: Option<&str>,
+) {
+    let mut project_block = theme::Catpuccin.block().title(project.project.clone());
+    if let Some(updated) = updated {
+        project_block = project_block.title(Line::from(updated.to_string()).right_aligned());
+    }
+    if let Some(err) = error {
+        project_block =
+            project_block.title_bottom(Line::styled(err.to_string(), theme::Catpuccin::red()));
+    }
+
+    let table = Table::new(
+        project.environments.iter().map(environment_row),
+        [
+            Constraint::Fill(2),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Fill(2),
+        ],
+    )
+    .header(
+        Row::new(["environment", "tier", "status", "ref", "sha", "by", "deployed", "manual jobs"])
+            .style(theme::Catpuccin::text().add_modifier(Modifier::BOLD)),
+    )
+    .block(project_block);
+
+    frame.render_widget(table, frame.area());
+}
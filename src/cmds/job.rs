@@ -0,0 +1,327 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{List, ListItem, ListState};
+use ratatui::Frame;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::events::*;
+use crate::fetchers::artifacts::{ArtifactSource, DownloadError};
+use crate::fetchers::job_control::JobCommand;
+use crate::fetchers::{Job, JobArtifactFileType};
+use crate::humanize;
+use crate::keymap;
+use crate::prompt::{Input, Prompt};
+use crate::theme;
+use crate::theme::Theme;
+
+/// Something to download from the job.
+enum Download {
+    /// the artifact archive or, if given, a single file of it
+    Archive(Option<String>),
+    /// any other artifact, e.g. a report, by its download path
+    Report(String),
+}
+
+/// What the prompt asks for.
+enum Asking {
+    /// the path of a file within the archive
+    File,
+    /// the local path to save the download to
+    Path(Download),
+    /// whether to retry or cancel the job, a stray key must not do it
+    Confirm(JobCommand),
+}
+
+struct App {
+    gapi: gitlab::AsyncGitlab,
+    project: String,
+    job: Job,
+    /// the artifacts followed by an entry for a single file of the archive
+    artifacts: ListState,
+    prompt: Option<(Prompt, Asking)>,
+    /// outcome of the last download
+    status: Option<String>,
+    status_sender: Sender<String>,
+    status_receiver: Receiver<String>,
+}
+
+impl App {
+    fn new(gapi: gitlab::AsyncGitlab, project: String, job: Job) -> Self {
+        let (status_sender, status_receiver) = channel(1);
+        let mut artifacts = ListState::default();
+        if !job.artifacts.is_empty() {
+            artifacts.select_first();
+        }
+
+        App {
+            gapi,
+            project,
+            job,
+            artifacts,
+            prompt: None,
+            status: None,
+            status_sender,
+            status_receiver,
+        }
+    }
+
+    fn update(&mut self) {
+        if let Ok(status) = self.status_receiver.try_recv() {
+            self.status = Some(status);
+        }
+    }
+
+    fn has_archive(&self) -> bool {
+        self.job
+            .artifacts
+            .iter()
+            .any(|a| a.file_type == JobArtifactFileType::ARCHIVE)
+    }
+
+    /// Asks where to save the selected artifact, for the entry after the
+    /// artifacts first which file of the archive to download.
+    fn download(&mut self) {
+        let Some(selected) = self.artifacts.selected() else {
+            return;
+        };
+        let Some(job_id) = self.job.rest_id() else {
+            return;
+        };
+        match self.job.artifacts.get(selected) {
+            Some(artifact) if artifact.file_type == JobArtifactFileType::ARCHIVE => {
+                self.ask_path(Download::Archive(None), format!("artifacts-{}.zip", job_id));
+            }
+            Some(artifact) => match &artifact.download_path {
+                Some(path) => self.ask_path(Download::Report(path.clone()), artifact.name.clone()),
+                None => self.status = Some(format!("{} can not be downloaded", artifact.name)),
+            },
+            None if self.has_archive() => {
+                let prompt = Prompt::new("file in the archive", String::new());
+                self.prompt = Some((prompt, Asking::File));
+            }
+            None => {}
+        }
+    }
+
+    fn ask_path(&mut self, download: Download, name: String) {
+        self.prompt = Some((Prompt::new("save to", name), Asking::Path(download)));
+    }
+
+    /// Passes a key on to the prompt and follows up on its answer.
+    fn answer(&mut self, key: &KeyEvent) {
+        let Some((prompt, _)) = &mut self.prompt else {
+            return;
+        };
+        let answer = match prompt.key(key) {
+            Input::Editing => return,
+            Input::Canceled => None,
+            Input::Done(answer) => Some(answer),
+        };
+        let Some((_, asking)) = self.prompt.take() else {
+            return;
+        };
+        let Some(answer) = answer.filter(|a| !a.trim().is_empty()) else {
+            return;
+        };
+
+        match asking {
+            Asking::File => {
+                let name = Path::new(&answer)
+                    .file_name()
+                    .map_or(answer.clone(), |n| n.to_string_lossy().to_string());
+                self.ask_path(Download::Archive(Some(answer)), name);
+            }
+            Asking::Path(download) => self.save(download, PathBuf::from(answer)),
+            Asking::Confirm(command) => {
+                if answer.trim().eq_ignore_ascii_case("y") {
+                    self.control(command);
+                }
+            }
+        }
+    }
+
+    /// Downloads in the background; the outcome is reported as status.
+    fn save(&mut self, download: Download, path: PathBuf) {
+        let Some(job_id) = self.job.rest_id() else {
+            return;
+        };
+
+        self.status = Some(format!("downloading to {} ...", path.display()));
+        let gapi = self.gapi.clone();
+        let project = self.project.clone();
+        let sender = self.status_sender.clone();
+        tokio::spawn(async move {
+            let downloaded = match &download {
+                Download::Archive(file) => {
+                    let source = ArtifactSource::Job(job_id);
+                    crate::fetchers::artifacts::download_artifacts(
+                        &gapi,
+                        &project,
+                        &source,
+                        file.as_deref(),
+                        &path,
+                    )
+                    .await
+                }
+                Download::Report(download_path) => {
+                    crate::fetchers::artifacts::download_report(&gapi, download_path, &path).await
+                }
+            };
+            let status = match downloaded {
+                Ok(()) => format!("saved {}", path.display()),
+                Err(DownloadError::Write(err)) => {
+                    format!("writing {} failed: {}", path.display(), err)
+                }
+                Err(err) => format!("download failed: {}", err),
+            };
+            _ = sender.send(status).await;
+        });
+    }
+
+    /// Retries or cancels the job; the outcome is reported as status.
+    fn control(&mut self, command: JobCommand) {
+        let Some(job_id) = self.job.rest_id() else {
+            return;
+        };
+
+        let (verb, noun) = match command {
+            JobCommand::Retry => ("retrying", "retry"),
+            JobCommand::Cancel => ("canceling", "cancel"),
+        };
+        self.status = Some(format!("{} {} ...", verb, self.job.name));
+        let gapi = self.gapi.clone();
+        let project = self.project.clone();
+        let sender = self.status_sender.clone();
+        tokio::spawn(async move {
+            let status = match crate::fetchers::job_control::control_job(
+                &gapi, &project, job_id, command,
+            )
+            .await
+            {
+                Ok(job) => format!("job {} is {}", job.id, job.status),
+                Err(err) => format!("{} failed: {}", noun, err),
+            };
+            _ = sender.send(status).await;
+        });
+    }
+
+    fn retry(&mut self) {
+        self.confirm(JobCommand::Retry);
+    }
+
+    fn cancel(&mut self) {
+        self.confirm(JobCommand::Cancel);
+    }
+
+    /// Asks before `command` is sent, only `y` confirms it.
+    fn confirm(&mut self, command: JobCommand) {
+        if self.job.rest_id().is_none() {
+            return;
+        }
+        let noun = match command {
+            JobCommand::Retry => "retry",
+            JobCommand::Cancel => "cancel",
+        };
+        let label = format!("{} {}? (y/n)", noun, self.job.name);
+        self.prompt = Some((Prompt::new(&label, String::new()), Asking::Confirm(command)));
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let title = match self.job.elapsed() {
+            Some(elapsed) => format!("{} ({})", self.job.name, humanize::duration(elapsed)),
+            None => self.job.name.clone(),
+        };
+        let keys = keymap::current();
+        let hints = format!(
+            "{}: download  {}: retry  {}: cancel",
+            keys.hint(Action::Download),
+            keys.hint(Action::Retry),
+            keys.hint(Action::Cancel)
+        );
+        let bottom = match (&self.prompt, &self.status) {
+            (Some((prompt, _)), _) => prompt.line().left_aligned(),
+            (None, Some(status)) => Line::from(status.clone()).right_aligned(),
+            (None, None) => Line::from(hints).right_aligned(),
+        };
+        let job_block = theme::Catpuccin
+            .block()
+            .title(Line::from(self.project.clone()).left_aligned())
+            .title(Line::from(title).right_aligned())
+            .title_bottom(bottom);
+
+        let mut items: Vec<ListItem> = match self.job.artifacts.is_empty() {
+            true => vec![ListItem::new("no artifacts").style(theme::Catpuccin::text())],
+            false => self
+                .job
+                .artifacts
+                .iter()
+                .map(|a| {
+                    ListItem::new(format!("{:<10} {}", format!("{:?}", a.file_type), a.name))
+                        .style(theme::Catpuccin::text())
+                })
+                .collect(),
+        };
+        if self.has_archive() {
+            items.push(
+                ListItem::new(format!("{:<10} a single file of the archive ...", "FILE"))
+                    .style(theme::Catpuccin::text()),
+            );
+        }
+        let list = List::new(items)
+            .block(job_block)
+            .highlight_style(Modifier::REVERSED);
+
+        frame.render_stateful_widget(list, frame.area(), &mut self.artifacts);
+    }
+}
+
+/// Details of a single job of a pipeline; lists its artifacts and allows to
+/// download them, or a single file of the archive, to a path the user enters.
+/// Returns once the user quits the view.
+pub async fn show(
+    gapi: gitlab::AsyncGitlab,
+    project: String,
+    job: Job,
+    event_handler: &mut EventHandler,
+) {
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
+    let viewport = ratatui::Viewport::Inline(10);
+    let mut terminal =
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
+            .expect("terminal setup to work");
+
+    let mut app = App::new(gapi, project, job);
+
+    loop {
+        app.update();
+        terminal
+            .draw(|frame| app.render(frame))
+            .expect("failed to draw frame");
+
+        match event_handler.next().await {
+            Event::Tick => {}
+            Event::Quit => break,
+            Event::Action(action) => match action {
+                Action::Quit => break,
+                Action::Up | Action::Prev => app.artifacts.select_previous(),
+                Action::Down | Action::Next => app.artifacts.select_next(),
+                Action::Download => app.download(),
+                Action::Retry => app.retry(),
+                Action::Cancel => app.cancel(),
+                _ => {}
+            },
+            Event::Key(key) => app.answer(&key),
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollUp => app.artifacts.select_previous(),
+                MouseEventKind::ScrollDown => app.artifacts.select_next(),
+                _ => {}
+            },
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
+        }
+        event_handler.text_input(app.prompt.is_some());
+    }
+    event_handler.text_input(false);
+    terminal.clear().expect("failed to clear terminal");
+}
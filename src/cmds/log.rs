@@ -0,0 +1,179 @@
+use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use std::io;
+use tokio::sync::mpsc::Receiver;
+
+use crate::events::*;
+use crate::fetchers::job_log::JobLogUpdate;
+use crate::fetchers::poll::Controller;
+use crate::fetchers::Job;
+use crate::humanize;
+use crate::theme;
+use crate::theme::Theme;
+
+/// Lines the mouse wheel scrolls at once.
+const WHEEL_LINES: usize = 3;
+
+struct App {
+    receiver: Receiver<Result<JobLogUpdate, String>>,
+    controller: Controller,
+    project: String,
+    job: Job,
+    /// `None` until the log is fetched
+    lines: Option<Vec<String>>,
+    /// the last request failed, the log is fetched again on the next poll
+    error: Option<String>,
+    /// when the last update was fetched
+    updated: Option<chrono::DateTime<chrono::Utc>>,
+    /// the final state of the job, the log is complete
+    finished: Option<String>,
+    /// the first line shown; `None` follows the end of the growing log
+    top: Option<usize>,
+    /// lines the log area had when last drawn
+    height: usize,
+}
+
+impl App {
+    fn new(gapi: gitlab::AsyncGitlab, project: String, job: Job, job_id: u64) -> Self {
+        let (controller, schedule) = crate::fetchers::poll::control();
+        let receiver = crate::fetchers::job_log::job_log(gapi, project.clone(), job_id, schedule);
+
+        App {
+            receiver,
+            controller,
+            project,
+            job,
+            lines: None,
+            error: None,
+            updated: None,
+            finished: None,
+            top: None,
+            height: 0,
+        }
+    }
+
+    fn update(&mut self) {
+        match self.receiver.try_recv() {
+            Ok(Ok(update)) => {
+                self.lines = Some(update.lines);
+                self.error = None;
+                self.updated = Some(update.fetched_at);
+                self.finished = update.finished;
+            }
+            Ok(Err(err)) => self.error = Some(err),
+            Err(_) => {}
+        }
+    }
+
+    /// The first line which shows the end of the log.
+    fn last_top(&self) -> usize {
+        let count = self.lines.as_ref().map_or(0, Vec::len);
+        count.saturating_sub(self.height)
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        let top = self.top.unwrap_or(self.last_top());
+        self.top = Some(top.saturating_sub(lines));
+    }
+
+    /// Follows the end of the log again once it is reached.
+    fn scroll_down(&mut self, lines: usize) {
+        let Some(top) = self.top else {
+            return;
+        };
+        self.top = match top + lines >= self.last_top() {
+            true => None,
+            false => Some(top + lines),
+        };
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let updated = match &self.finished {
+            Some(status) => Some(format!("job {}", status)),
+            None => self
+                .updated
+                .map(|u| humanize::updated(u, self.controller.paused())),
+        };
+        let mut block = theme::Catpuccin
+            .block()
+            .title(Line::from(format!("{}  {}", self.project, self.job.name)).left_aligned());
+        if let Some(updated) = updated {
+            block = block.title(Line::from(updated).right_aligned());
+        }
+        if let Some(err) = &self.error {
+            block = block.title_bottom(Line::styled(err.clone(), theme::Catpuccin::red()));
+        }
+        let area = block.inner(frame.area());
+        frame.render_widget(block, frame.area());
+        self.height = area.height as usize;
+
+        let Some(lines) = &self.lines else {
+            frame.render_widget(
+                Line::from("fetching the log ...").style(theme::Catpuccin::text()),
+                area,
+            );
+            return;
+        };
+        let top = self.top.unwrap_or(self.last_top()).min(self.last_top());
+        let shown: Vec<Line> = lines
+            .iter()
+            .skip(top)
+            .take(self.height)
+            .map(|l| Line::from(l.clone()).style(theme::Catpuccin::text()))
+            .collect();
+        frame.render_widget(Paragraph::new(shown), area);
+    }
+}
+
+/// The log of a job, following its end while the job runs. The wheel and the
+/// arrow keys scroll it. Returns once the user quits the view.
+pub async fn show(
+    gapi: gitlab::AsyncGitlab,
+    project: String,
+    job: Job,
+    event_handler: &mut EventHandler,
+) {
+    let Some(job_id) = job.rest_id() else {
+        return;
+    };
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
+    let viewport = ratatui::Viewport::Inline(20);
+    let mut terminal =
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
+            .expect("terminal setup to work");
+
+    let mut app = App::new(gapi, project, job, job_id);
+
+    loop {
+        app.update();
+        terminal
+            .draw(|frame| app.render(frame))
+            .expect("failed to draw frame");
+
+        let page = app.height.max(1);
+        match event_handler.next().await {
+            Event::Tick => {}
+            Event::Quit => break,
+            Event::Action(action) => match action {
+                Action::Quit => break,
+                Action::Refresh => app.controller.refresh(),
+                Action::Pause => app.controller.toggle_pause(),
+                Action::Up => app.scroll_up(1),
+                Action::Down => app.scroll_down(1),
+                Action::Prev => app.scroll_up(page),
+                Action::Next => app.scroll_down(page),
+                _ => {}
+            },
+            Event::Key(_) => {}
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollUp => app.scroll_up(WHEEL_LINES),
+                MouseEventKind::ScrollDown => app.scroll_down(WHEEL_LINES),
+                _ => {}
+            },
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
+        }
+    }
+    terminal.clear().expect("failed to clear terminal");
+}
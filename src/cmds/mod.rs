@@ -1,2 +1,40 @@
-pub mod pipelines;
+pub mod artifacts;
+pub mod config;
+pub mod dash;
+pub mod envs;
+pub mod job;
+pub mod log;
+pub mod pick;
 pub mod pipeline;
+pub mod pipelines;
+pub mod run;
+pub mod serve;
+pub mod stats;
+pub mod status;
+pub mod test_report;
+pub mod whoami;
+
+/// Why a command gave up after the terminal was set up for the views; main
+/// restores the terminal before it reports this and exits.
+#[derive(Debug)]
+pub struct Failure {
+    pub message: String,
+    /// 2 for invalid arguments, 1 for failed requests
+    pub code: i32,
+}
+
+impl Failure {
+    pub fn usage(message: impl Into<String>) -> Self {
+        Failure {
+            message: message.into(),
+            code: 2,
+        }
+    }
+
+    pub fn request(message: impl Into<String>) -> Self {
+        Failure {
+            message: message.into(),
+            code: 1,
+        }
+    }
+}
@@ -0,0 +1,353 @@
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use ratatui::Frame;
+use std::future::Future;
+use std::io;
+
+use clap::Args;
+
+use crate::events::*;
+use crate::gitlab_ref::*;
+use crate::humanize;
+use crate::theme;
+use crate::theme::Theme;
+
+/// Offered first among the branches, picks the project as a whole.
+const ALL_BRANCHES: &str = "* all branches";
+
+#[derive(Debug, Args)]
+pub struct PickArgs {
+    /// Show a chart of the pipeline durations below the pipelines
+    #[arg(long)]
+    durations: bool,
+    /// Stop fetching jobs of a pipeline after this many
+    #[arg(long, default_value_t = crate::fetchers::JOB_LIMIT)]
+    max_jobs: usize,
+}
+
+struct Picker {
+    title: String,
+    items: Vec<String>,
+    /// shown dimmed after the item of the same index, the query ignores them
+    notes: Vec<String>,
+    query: String,
+    /// indices into `items` matching the query, the best match first
+    matches: Vec<usize>,
+    /// index into `matches`
+    selected: usize,
+    /// fetching the items failed, only the ones at hand are offered
+    error: Option<String>,
+}
+
+impl Picker {
+    fn new(title: String, items: Vec<String>) -> Self {
+        let mut picker = Picker {
+            title,
+            items,
+            notes: Vec::new(),
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            error: None,
+        };
+        picker.filter();
+        picker
+    }
+
+    fn filter(&mut self) {
+        self.selected = 0;
+        // keep the order of the items, e.g. the recent ones first
+        if self.query.is_empty() {
+            self.matches = (0..self.items.len()).collect();
+            return;
+        }
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| score(&self.query, item).map(|s| (-s, i)))
+            .collect();
+        // the order of the items breaks ties
+        scored.sort();
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+    }
+
+    fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.filter();
+    }
+
+    fn pop(&mut self) {
+        self.query.pop();
+        self.filter();
+    }
+
+    fn select(&mut self, offset: isize) {
+        let selected = self.selected.saturating_add_signed(offset);
+        if selected < self.matches.len() {
+            self.selected = selected;
+        }
+    }
+
+    /// Index of the selected item.
+    fn selection(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let count = format!("{}/{}", self.matches.len(), self.items.len());
+        let hint = "type to filter, ↑/↓ select, enter picks";
+        let mut block = theme::Catpuccin
+            .block()
+            .title(self.title.clone())
+            .title(Line::from(count).right_aligned())
+            .title_bottom(Line::from(hint).right_aligned());
+        if let Some(err) = &self.error {
+            block = block.title_bottom(Line::styled(err.clone(), theme::Catpuccin::red()));
+        }
+        let area = block.inner(frame.area());
+        frame.render_widget(block, frame.area());
+
+        let [input_area, list_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        let input = Line::from(vec![
+            Span::styled("> ", theme::Catpuccin::blue()),
+            Span::styled(self.query.clone(), theme::Catpuccin::text()),
+        ]);
+        frame.render_widget(Paragraph::new(input), input_area);
+
+        // keep the selection visible
+        let height = list_area.height as usize;
+        let offset = (self.selected + 1).saturating_sub(height);
+        let lines: Vec<Line> = self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .take(height)
+            .map(|(n, i)| {
+                let style = match n == self.selected {
+                    true => theme::Catpuccin::blue().add_modifier(Modifier::BOLD),
+                    false => theme::Catpuccin::text(),
+                };
+                let mut line = Line::styled(self.items[*i].clone(), style);
+                if let Some(note) = self.notes.get(*i).filter(|n| !n.is_empty()) {
+                    let dim = theme::Catpuccin::text().add_modifier(Modifier::DIM);
+                    line.push_span(Span::styled(format!("  {}", note), dim));
+                }
+                line
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), list_area);
+    }
+}
+
+/// Score of `item` for `query` if the characters of the query appear in it in
+/// order, ignoring case. Consecutive characters and characters starting a
+/// path segment or word score higher, shorter items win ties.
+fn score(query: &str, item: &str) -> Option<i64> {
+    let chars: Vec<char> = item.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last: Option<usize> = None;
+    for q in query.chars().map(|c| c.to_ascii_lowercase()) {
+        let i = (next..chars.len()).find(|&i| chars[i] == q)?;
+        score += 10;
+        if i == 0 || matches!(chars[i - 1], '/' | '-' | '_' | '.' | ' ') {
+            score += 30;
+        }
+        if last.is_some_and(|l| l + 1 == i) {
+            score += 20;
+        }
+        last = Some(i);
+        next = i + 1;
+    }
+    Some(score * 1000 - chars.len() as i64)
+}
+
+pub async fn run(gapi: gitlab::AsyncGitlab, args: &PickArgs) {
+    let mut event_handler = EventHandler::new(250);
+    let Some(gitlab_ref) = pick_ref(&gapi, &mut event_handler).await else {
+        return;
+    };
+    crate::recent::record(&gitlab_ref);
+    crate::cmds::pipelines::show(
+        Some(gapi),
+        gitlab_ref,
+        args.durations,
+        args.max_jobs,
+        &mut event_handler,
+    )
+    .await;
+}
+
+/// Lets the user pick one of the recent refs or one of their projects and
+/// then one of its branches.
+pub async fn pick_ref(
+    gapi: &gitlab::AsyncGitlab,
+    event_handler: &mut EventHandler,
+) -> Option<GitlabRef> {
+    let recent = crate::recent::load();
+    let mut labels: Vec<String> = recent.iter().map(|r| r.gitlab_ref.clone()).collect();
+    let notes = recent
+        .iter()
+        .map(|r| {
+            let age = chrono::Utc::now().signed_duration_since(r.at);
+            format!("(watched {} ago)", humanize::age(age))
+        })
+        .collect();
+    let choices = async move {
+        // the recent refs are still offered
+        let (projects, error) = match crate::fetchers::projects::member_projects(gapi).await {
+            Ok(projects) => (projects, None),
+            Err(err) => (Vec::new(), Some(err.to_string())),
+        };
+        labels.extend(projects.iter().cloned());
+        Choices {
+            labels,
+            notes,
+            values: projects,
+            error,
+        }
+    };
+    let (index, projects) = pick("project".to_string(), choices, event_handler).await?;
+    let project = match index.checked_sub(recent.len()) {
+        None => return Some(GitlabRef::from(recent[index].gitlab_ref.as_str())),
+        Some(i) => projects[i].clone(),
+    };
+
+    let branches = async {
+        // the project as a whole is still offered
+        let (mut branches, error) = match crate::fetchers::projects::branches(gapi, &project).await
+        {
+            Ok(branches) => (branches, None),
+            Err(err) => (Vec::new(), Some(err.to_string())),
+        };
+        branches.insert(0, ALL_BRANCHES.to_string());
+        Choices {
+            labels: branches.clone(),
+            notes: Vec::new(),
+            values: branches,
+            error,
+        }
+    };
+    let (index, branches) = pick(format!("branch of {}", project), branches, event_handler).await?;
+    match index {
+        0 => Some(GitlabRef::Repo(project)),
+        _ => Some(GitlabRef::Branch(project, branches[index].clone())),
+    }
+}
+
+/// What [`pick`] offers to pick from.
+struct Choices<T> {
+    labels: Vec<String>,
+    /// shown after the label of the same index, e.g. when a ref was watched
+    notes: Vec<String>,
+    values: T,
+    /// fetching the labels failed, only the ones at hand are offered
+    error: Option<String>,
+}
+
+/// Fuzzy finder over the labels of `choices`, returns the index of the
+/// picked label along with the values; `None` if the user quits.
+async fn pick<T>(
+    title: String,
+    choices: impl Future<Output = Choices<T>>,
+    event_handler: &mut EventHandler,
+) -> Option<(usize, T)> {
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
+    let viewport = ratatui::Viewport::Inline(15);
+    let mut terminal =
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
+            .expect("terminal setup to work");
+
+    let loading = theme::Catpuccin.loading(title.clone());
+    terminal
+        .draw(|frame| frame.render_widget(loading, frame.area()))
+        .expect("failed to draw frame");
+    let choices = choices.await;
+    let mut picker = Picker::new(title, choices.labels);
+    picker.notes = choices.notes;
+    picker.error = choices.error;
+    let values = choices.values;
+
+    event_handler.text_input(true);
+    let selection = loop {
+        terminal
+            .draw(|frame| picker.render(frame))
+            .expect("failed to draw frame");
+
+        match event_handler.next().await {
+            Event::Quit => break None,
+            Event::Key(key) => match key.code {
+                KeyCode::Esc => break None,
+                KeyCode::Enter => {
+                    if let Some(selection) = picker.selection() {
+                        break Some(selection);
+                    }
+                }
+                KeyCode::Up => picker.select(-1),
+                KeyCode::Down => picker.select(1),
+                KeyCode::Backspace => picker.pop(),
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    picker.push(c)
+                }
+                _ => {}
+            },
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollUp => picker.select(-1),
+                MouseEventKind::ScrollDown => picker.select(1),
+                _ => {}
+            },
+            Event::Tick | Event::Action(_) => {}
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
+        }
+    };
+    event_handler.text_input(false);
+    terminal.clear().expect("failed to clear terminal");
+    selection.map(|index| (index, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_matches() {
+        let items = ["group/docs", "group/project", "other/gitlab-monitor", "group/pipelines"]
+            .map(String::from)
+            .to_vec();
+        let mut picker = Picker::new("project".to_string(), items);
+        assert_eq!(picker.matches.len(), 4);
+
+        for c in "gmon".chars() {
+            picker.push(c);
+        }
+        assert_eq!(picker.selection(), Some(2));
+
+        picker.pop();
+        picker.pop();
+        picker.pop();
+        for c in "pro".chars() {
+            picker.push(c);
+        }
+        assert_eq!(picker.selection(), Some(1));
+        assert!(score("xyz", "group/project").is_none());
+    }
+
+    #[test]
+    fn ignores_the_notes() {
+        let items = ["group/project@main", "group/docs"]
+            .map(String::from)
+            .to_vec();
+        let mut picker = Picker::new("project".to_string(), items);
+        picker.notes = vec!["(watched 3h ago)".to_string()];
+
+        for c in "watched".chars() {
+            picker.push(c);
+        }
+        assert_eq!(picker.selection(), None);
+    }
+}
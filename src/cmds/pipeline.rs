@@ -1,65 +1,368 @@
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, BorderType};
+use ratatui::widgets::{Block, Paragraph};
 use ratatui::Frame;
+use std::collections::HashMap;
 use std::io;
 
 use clap::Args;
 
+use crate::cmds::Failure;
 use crate::events::*;
+use crate::fetchers::history::HistoryQueryArgs;
+use crate::fetchers::jobs::PipelineStatusEnum;
+use crate::fetchers::poll::Controller;
+use crate::fetchers::projects::{FetchError, NotFound};
 use crate::fetchers::CiJobStatus;
 use crate::gitlab_ref::*;
+use crate::humanize;
 use crate::theme;
 use crate::theme::Theme;
 
 #[derive(Debug, Args)]
 pub struct PipelineArgs {
+    /// `-` for the ref watched last
     gitlab_ref: GitlabRef,
+    /// Stop fetching jobs of a pipeline after this many
+    #[arg(long, default_value_t = crate::fetchers::JOB_LIMIT)]
+    max_jobs: usize,
 }
 
-use crate::fetchers::{JobQueryParams, Project};
+use crate::fetchers::{Job, JobQueryParams, Project, TestSummary};
+
+/// Number of past pipelines the job durations are compared against.
+const HISTORY_LENGTH: usize = 20;
+
+/// Jobs taking this many times longer than their median are flagged.
+const SLOW_FACTOR: f64 = 2.0;
+
+/// What the user chose to look at in more detail.
+enum Selection {
+    /// a job of the project, which is a downstream one for their jobs
+    Job(String, Job),
+    /// the log of a job, see [`Selection::Job`] for the project
+    Log(String, Job),
+    Tests(String, TestSummary),
+}
+
+/// The pipeline of an expanded trigger job, fetched only while expanded.
+struct Expanded {
+    receiver: tokio::sync::mpsc::Receiver<Result<Project, FetchError>>,
+    controller: Controller,
+    /// `None` until the first update arrives
+    project: Option<Project>,
+    /// the last request failed, the project is kept until the next poll
+    error: Option<String>,
+}
 
 struct App {
-    receiver: tokio::sync::mpsc::Receiver<Project>,
+    /// `None` when replaying a recorded session
+    gapi: Option<gitlab::AsyncGitlab>,
+    receiver: tokio::sync::mpsc::Receiver<Result<Project, FetchError>>,
+    controller: Controller,
     /// there is only one project with one pipeline in here
     project: Option<Project>,
+    /// the project does not exist or is not accessible
+    missing: Option<NotFound>,
+    /// the last request failed, the pipeline is fetched again on the next poll
+    error: Option<String>,
+    /// keeps the last update for the next start
+    cache: crate::cache::File,
+    /// the project is the cached one, no update was received yet
+    stale: bool,
+    /// shown until the first update arrives
+    title: String,
+    /// when the last update was fetched
+    updated: Option<chrono::DateTime<chrono::Utc>>,
+    /// only known once the ref of the pipeline is known
+    medians_receiver: Option<tokio::sync::mpsc::Receiver<HashMap<String, i64>>>,
+    /// historical median duration per job name
+    medians: HashMap<String, i64>,
+    /// index into the visible jobs, see [`visible_jobs`]
+    selected: usize,
+    /// the downstream pipelines by the id of their trigger job, at any depth
+    expanded: HashMap<String, Expanded>,
+    /// jobs fetched per pipeline, the downstream ones included
+    job_limit: usize,
+    /// index of the job the mouse is over
+    hovered: Option<usize>,
+    /// shows what the job symbols mean
+    legend: bool,
+    /// where the jobs were drawn, for the mouse
+    hits: Vec<(Rect, usize)>,
 }
 
 impl App {
-    fn new(gapi: gitlab::AsyncGitlab, args: &PipelineArgs) -> Self {
-        let receiver = match &args.gitlab_ref {
-            GitlabRef::Repo(repo) => crate::fetchers::jobs_pipelines(
-                gapi.clone(),
-                JobQueryParams::new(repo.clone()).with_count(1),
-            ),
-            GitlabRef::Branch(repo, branch) => crate::fetchers::jobs_pipelines(
-                gapi.clone(),
-                JobQueryParams::new(repo.clone())
-                    .with_reference(branch.clone())
-                    .with_count(1),
-            ),
-        };
+    fn new(gapi: Option<gitlab::AsyncGitlab>, params: JobQueryParams) -> Self {
+        let (controller, schedule) = crate::fetchers::poll::control();
+        let receiver = crate::session::updates("pipeline", &params, || {
+            let gapi = gapi.clone().expect("gitlab outside of a replay");
+            crate::fetchers::jobs_pipelines(gapi, params.clone(), schedule)
+        });
+        let cache = crate::cache::File::new("pipeline", &params);
+        let project: Option<Project> = cache.load();
 
         App {
+            gapi,
             receiver,
-            project: None,
+            controller,
+            stale: project.is_some(),
+            project,
+            missing: None,
+            error: None,
+            cache,
+            title: params.project().to_string(),
+            updated: None,
+            medians_receiver: None,
+            medians: HashMap::new(),
+            selected: 0,
+            expanded: HashMap::new(),
+            job_limit: params.job_limit(),
+            hovered: None,
+            legend: false,
+            hits: Vec::new(),
         }
     }
 
     fn update(&mut self) {
         // check if there is a new project update
-        if let Ok(p) = self.receiver.try_recv() {
-            self.project = Some(p);
+        match self.receiver.try_recv() {
+            Ok(Ok(p)) => {
+                self.update_project(p);
+                self.error = None;
+            }
+            Ok(Err(FetchError::NotFound(missing))) => self.missing = Some(missing),
+            Ok(Err(FetchError::Transient(err))) => self.error = Some(err),
+            Err(_) => {}
+        }
+
+        if let Some(Ok(medians)) = self.medians_receiver.as_mut().map(|r| r.try_recv()) {
+            self.medians = medians;
+        }
+
+        for expanded in self.expanded.values_mut() {
+            // a missing downstream project keeps loading, it is shown dimmed
+            match expanded.receiver.try_recv() {
+                Ok(Ok(project)) => {
+                    expanded.project = Some(project);
+                    expanded.error = None;
+                }
+                Ok(Err(FetchError::Transient(err))) => expanded.error = Some(err),
+                Ok(Err(FetchError::NotFound(_))) | Err(_) => {}
+            }
+        }
+    }
+
+    fn update_project(&mut self, p: Project) {
+        if self.medians_receiver.is_none() {
+            if let (Some(gapi), Some(pipeline)) = (&self.gapi, p.pipelines.first()) {
+                let params = HistoryQueryArgs::new(p.full_path.clone(), HISTORY_LENGTH)
+                    .with_reference(pipeline.git_ref.clone());
+                self.medians_receiver =
+                    Some(crate::fetchers::history::job_medians(gapi.clone(), params));
+            }
         }
+        self.cache.keep(&p);
+        self.updated = Some(p.fetched_at);
+        self.project = Some(p);
+        self.stale = false;
+    }
+
+    /// The fetched downstream projects by the id of their trigger job.
+    fn downstreams(&self) -> HashMap<String, Option<&Project>> {
+        self.expanded
+            .iter()
+            .map(|(id, e)| (id.clone(), e.project.as_ref()))
+            .collect()
+    }
+
+    /// The project and the job selected.
+    fn selected_job(&self) -> Option<(String, Job)> {
+        let project = self.project.as_ref()?;
+        let downstreams = self.downstreams();
+        visible_jobs(project, &downstreams)
+            .get(self.selected)
+            .map(|(project, job)| (project.to_string(), (*job).clone()))
     }
 
-    fn render(&self, frame: &mut Frame) {
-        if let Some(p) = &self.project {
-            render(frame, p);
+    fn select_next(&mut self) {
+        let count = self
+            .project
+            .as_ref()
+            .map_or(0, |p| visible_jobs(p, &self.downstreams()).len());
+        if self.selected + 1 < count {
+            self.selected += 1;
         }
     }
+
+    fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Expands the downstream pipeline of the selected trigger job and starts
+    /// to fetch it, or collapses it again.
+    fn toggle_downstream(&mut self) {
+        let Some((_, job)) = self.selected_job() else {
+            return;
+        };
+        let Some(downstream) = &job.downstream else {
+            return;
+        };
+        if self.expanded.contains_key(&job.id) {
+            self.collapse(&job.id);
+            return;
+        }
+
+        let params = JobQueryParams::new(downstream.project.clone())
+            .with_pipeline(downstream.iid.clone())
+            .with_job_limit(self.job_limit);
+        let (mut controller, schedule) = crate::fetchers::poll::control();
+        if self.controller.paused() {
+            controller.toggle_pause();
+        }
+        let gapi = self.gapi.clone();
+        let receiver = crate::session::updates("pipeline", &params, || {
+            let gapi = gapi.expect("gitlab outside of a replay");
+            crate::fetchers::jobs_pipelines(gapi, params.clone(), schedule)
+        });
+        let expanded = Expanded {
+            receiver,
+            controller,
+            project: None,
+            error: None,
+        };
+        self.expanded.insert(job.id, expanded);
+    }
+
+    /// Stops fetching the downstream pipeline of the trigger job `id` and the
+    /// ones expanded within it.
+    fn collapse(&mut self, id: &str) {
+        let Some(expanded) = self.expanded.remove(id) else {
+            return;
+        };
+        let nested: Vec<String> = expanded
+            .project
+            .iter()
+            .flat_map(|p| p.pipelines.iter())
+            .flat_map(|p| p.stages.iter())
+            .flat_map(|s| s.jobs.iter())
+            .map(|j| j.id.clone())
+            .collect();
+        for id in nested {
+            self.collapse(&id);
+        }
+    }
+
+    fn refresh(&self) {
+        self.controller.refresh();
+        for expanded in self.expanded.values() {
+            expanded.controller.refresh();
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        self.controller.toggle_pause();
+        for expanded in self.expanded.values_mut() {
+            expanded.controller.toggle_pause();
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        if let Some(missing) = &self.missing {
+            crate::cmds::pipelines::render_not_found(frame, frame.area(), missing);
+            return;
+        }
+        // the downstream pipelines fail alike, one error is enough
+        let error = self
+            .error
+            .as_ref()
+            .or_else(|| self.expanded.values().find_map(|e| e.error.as_ref()));
+        let Some(p) = &self.project else {
+            let mut loading = theme::Catpuccin.loading(self.title.clone());
+            if let Some(err) = error {
+                loading = loading.title_bottom(Line::styled(err.clone(), theme::Catpuccin::red()));
+            }
+            frame.render_widget(loading, frame.area());
+            return;
+        };
+        let updated = match self.stale {
+            true => Some(humanize::STALE.to_string()),
+            false => self
+                .updated
+                .map(|u| humanize::updated(u, self.controller.paused())),
+        };
+        let downstreams = self
+            .expanded
+            .iter()
+            .map(|(id, e)| (id.clone(), e.project.as_ref()))
+            .collect();
+        self.hits = render(
+            frame,
+            p,
+            &self.medians,
+            self.selected,
+            &downstreams,
+            self.hovered,
+            self.legend,
+            updated.as_deref(),
+            error.map(String::as_str),
+        );
+    }
+}
+
+pub async fn run(gapi: Option<gitlab::AsyncGitlab>, args: &PipelineArgs) -> Result<(), Failure> {
+    let gitlab_ref = crate::recent::watch(&args.gitlab_ref).map_err(Failure::usage)?;
+    let params = match &gitlab_ref {
+        GitlabRef::Repo(repo) => JobQueryParams::new(repo.clone()).with_count(1),
+        GitlabRef::Branch(repo, branch) => JobQueryParams::new(repo.clone())
+            .with_reference(branch.clone())
+            .with_count(1),
+    };
+    let params = params.with_job_limit(args.max_jobs);
+    let mut event_handler = EventHandler::new(250);
+
+    show(gapi, params, &mut event_handler).await;
+    Ok(())
 }
 
-pub async fn run(gapi: gitlab::AsyncGitlab, args: &PipelineArgs) {
+/// Live view of the stages and jobs of the single pipeline described by `params`.
+/// Returns once the user quits the view.
+pub async fn show(
+    gapi: Option<gitlab::AsyncGitlab>,
+    params: JobQueryParams,
+    event_handler: &mut EventHandler,
+) {
+    let mut app = App::new(gapi.clone(), params);
+
+    while let Some(selection) = select(&mut app, event_handler).await {
+        let project = app.project.as_ref().expect("selection without project");
+        // the details are not part of a recorded session
+        let Some(gapi) = &gapi else {
+            continue;
+        };
+        match selection {
+            Selection::Job(project, job) => {
+                crate::cmds::job::show(gapi.clone(), project, job, event_handler).await
+            }
+            Selection::Log(project, job) => {
+                crate::cmds::log::show(gapi.clone(), project, job, event_handler).await
+            }
+            Selection::Tests(iid, summary) => {
+                crate::cmds::test_report::show(
+                    gapi.clone(),
+                    project.full_path.clone(),
+                    iid,
+                    summary,
+                    event_handler,
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Shows the pipeline until the user either quits (`None`) or opens one of
+/// the jobs, the log of one or the test report.
+async fn select(app: &mut App, event_handler: &mut EventHandler) -> Option<Selection> {
     let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
     ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
     let viewport = ratatui::Viewport::Inline(15);
@@ -67,9 +370,6 @@ pub async fn run(gapi: gitlab::AsyncGitlab, args: &PipelineArgs) {
         ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
             .expect("terminal setup to work");
 
-    let mut app = App::new(gapi, args);
-    let mut event_handler = EventHandler::new(250);
-
     loop {
         app.update();
         terminal
@@ -78,39 +378,193 @@ pub async fn run(gapi: gitlab::AsyncGitlab, args: &PipelineArgs) {
 
         match event_handler.next().await {
             Event::Tick => {}
-            Event::Quit => break,
-            Event::Key(_) => break,
-            Event::Resize(_, _) => terminal.autoresize().expect("failed to resize"),
+            Event::Quit => return None,
+            Event::Action(action) => match action {
+                Action::Quit => return None,
+                Action::Refresh => app.refresh(),
+                Action::Pause => app.toggle_pause(),
+                Action::Prev | Action::Up => app.select_prev(),
+                Action::Next | Action::Down => app.select_next(),
+                Action::Open => {
+                    if let Some((project, job)) = app.selected_job() {
+                        terminal.clear().expect("failed to clear terminal");
+                        return Some(Selection::Job(project, job));
+                    }
+                }
+                Action::Log => {
+                    if let Some((project, job)) = app.selected_job() {
+                        terminal.clear().expect("failed to clear terminal");
+                        return Some(Selection::Log(project, job));
+                    }
+                }
+                Action::Toggle => app.toggle_downstream(),
+                Action::ToggleLegend => app.legend = !app.legend,
+                Action::Tests => {
+                    let pipeline = app.project.as_ref().and_then(|p| p.pipelines.first());
+                    if let Some((iid, Some(tests))) = pipeline.map(|p| (&p.iid, &p.tests)) {
+                        let selection = Selection::Tests(iid.clone(), tests.clone());
+                        terminal.clear().expect("failed to clear terminal");
+                        return Some(selection);
+                    }
+                }
+                _ => {}
+            },
+            Event::Key(_) => {}
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let Some(index) = hit(&app.hits, &mouse) else {
+                        continue;
+                    };
+                    app.selected = index;
+                    if let Some((project, job)) = app.selected_job() {
+                        terminal.clear().expect("failed to clear terminal");
+                        return Some(Selection::Log(project, job));
+                    }
+                }
+                MouseEventKind::Moved => app.hovered = hit(&app.hits, &mouse),
+                MouseEventKind::ScrollUp => app.select_prev(),
+                MouseEventKind::ScrollDown => app.select_next(),
+                _ => {}
+            },
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
         }
     }
 }
 
-fn render(frame: &mut Frame, project: &Project) {
-    assert_eq!(project.pipelines.len(), 1);
+/// The jobs in the order they are shown: the jobs of a stage followed by the
+/// jobs of the expanded downstream pipelines they triggered, along with the
+/// project of each job.
+fn visible_jobs<'a>(
+    project: &'a Project,
+    downstreams: &HashMap<String, Option<&'a Project>>,
+) -> Vec<(&'a str, &'a Job)> {
+    let mut jobs = Vec::new();
+    for stage in project.pipelines.iter().flat_map(|p| &p.stages) {
+        jobs.extend(stage.jobs.iter().map(|j| (project.full_path.as_str(), j)));
+        for job in &stage.jobs {
+            if let Some(Some(downstream)) = downstreams.get(&job.id) {
+                jobs.extend(visible_jobs(downstream, downstreams));
+            }
+        }
+    }
+    jobs
+}
 
-    let project_block = theme::Catpuccin.block().title(project.full_path.clone());
+/// `downstreams` are the expanded downstream pipelines by the id of their
+/// trigger job, `None` while they are loading.
+#[allow(clippy::too_many_arguments)]
+fn render(
+    frame: &mut Frame,
+    project: &Project,
+    medians: &HashMap<String, i64>,
+    selected: usize,
+    downstreams: &HashMap<String, Option<&Project>>,
+    hovered: Option<usize>,
+    legend: bool,
+    updated: Option<&str>,
+    error: Option<&str>,
+) -> Vec<(Rect, usize)> {
+    let mut project_block = theme::Catpuccin.block().title(project.full_path.clone());
+    if let Some(updated) = updated {
+        project_block = project_block.title(Line::from(updated.to_string()).right_aligned());
+    }
+    if legend {
+        project_block = project_block.title_bottom(self::legend().left_aligned());
+    }
+    if let Some(err) = error {
+        let err = Line::styled(err.to_string(), theme::Catpuccin::red()).left_aligned();
+        project_block = project_block.title_bottom(err);
+    }
     frame.render_widget(&project_block, frame.area());
 
     let project_content_area = project_block.inner(frame.area());
 
-    let pipeline = &project.pipelines[0];
+    // the ref has no pipeline (yet) or the pipeline does not exist
+    let Some(pipeline) = project.pipelines.first() else {
+        let none = Paragraph::new(Line::styled("no pipeline", theme::Catpuccin::text()));
+        frame.render_widget(none, project_content_area);
+        return Vec::new();
+    };
     let branch_block = theme::Catpuccin
         .block()
         .title(Line::from(pipeline.git_ref.clone()).left_aligned())
         .title(Line::from(pipeline.name.clone()).right_aligned());
+    let branch_block = match &pipeline.tests {
+        Some(tests) => {
+            let style = match tests.total.failed + tests.total.errored {
+                0 => theme::Catpuccin::text(),
+                _ => theme::Catpuccin::red(),
+            };
+            let summary = format!(
+                "tests: {} ({})",
+                crate::cmds::test_report::counts_line(&tests.total),
+                crate::keymap::current().hint(Action::Tests)
+            );
+            branch_block.title_bottom(Line::styled(summary, style).right_aligned())
+        }
+        None => branch_block,
+    };
+    let hovered_job = hovered.and_then(|i| visible_jobs(project, downstreams).get(i).copied());
+    let branch_block = match hovered_job {
+        Some((_, job)) => {
+            let hint = match job.elapsed() {
+                Some(elapsed) => format!("{}  {}", job.name, humanize::duration(elapsed)),
+                None => job.name.clone(),
+            };
+            branch_block.title_bottom(Line::from(hint).left_aligned())
+        }
+        None => branch_block,
+    };
     frame.render_widget(&branch_block, project_content_area);
 
+    // index of the first job of the current stage among the visible jobs
+    let mut offset = 0;
+    let stage_lines: Vec<(usize, Vec<Line>, Vec<JobHits>)> = pipeline
+        .stages
+        .iter()
+        .map(|stage| {
+            let line = Line::from_iter(stage.jobs.iter().enumerate().map(|(i, j)| {
+                if offset + i == selected {
+                    job_span(j).reversed()
+                } else {
+                    job_span(j)
+                }
+            }));
+            let first = offset;
+            let mut next = offset + stage.jobs.len();
+
+            let durations = stage
+                .jobs
+                .iter()
+                .filter_map(|j| duration_line(j, medians.get(&j.name).copied()));
+            let mut lines: Vec<Line> = std::iter::once(line).chain(durations).collect();
+            let mut hits = vec![Vec::new(); lines.len()];
+            for job in &stage.jobs {
+                let downstream = downstream_lines(job, downstreams, selected, &mut next, 0);
+                for (line, line_hits) in downstream {
+                    lines.push(line);
+                    hits.push(line_hits);
+                }
+            }
+            offset = next;
+            (first, lines, hits)
+        })
+        .collect();
+
     let stage_layouts = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(
-            pipeline
-                .stages
-                .iter()
-                .map(|s| Constraint::Min(u16::try_from(s.jobs.len()).expect("FUCK TOO MANY") + 2)),
-        )
+        .constraints(stage_lines.iter().map(|(_, l, _)| {
+            Constraint::Min(u16::try_from(l.len() + 1).unwrap_or(u16::MAX))
+        }))
         .split(branch_block.inner(project_content_area));
 
-    for (stage, stage_layout) in pipeline.stages.iter().zip(stage_layouts.iter()) {
+    let mut hits = Vec::new();
+    for ((stage, (offset, lines, line_hits)), stage_layout) in pipeline
+        .stages
+        .iter()
+        .zip(stage_lines)
+        .zip(stage_layouts.iter())
+    {
         let [stage_name_layout, fill, line_layout] = Layout::horizontal([
             Constraint::Max(20),
             Constraint::Length(5),
@@ -123,14 +577,233 @@ fn render(frame: &mut Frame, project: &Project) {
             stage_name_layout,
         );
         frame.render_widget(Block::new(), fill);
+        frame.render_widget(Text::from(lines), line_layout);
+
+        // the job dots of the first line and the duration lines below
+        let mut x = line_layout.x;
+        let mut y = line_layout.y + 1;
+        for (i, job) in stage.jobs.iter().enumerate() {
+            let width = job_span(job).width() as u16;
+            let area = Rect::new(x, line_layout.y, width, 1).intersection(line_layout);
+            hits.push((area, offset + i));
+            x += width;
+        }
+        for (i, job) in stage.jobs.iter().enumerate() {
+            if job.elapsed().is_some() {
+                let area = Rect::new(line_layout.x, y, line_layout.width, 1).intersection(line_layout);
+                hits.push((area, offset + i));
+                y += 1;
+            }
+        }
+        // the job dots of the downstream pipelines
+        for (row, line_hits) in line_hits.iter().enumerate() {
+            for &(column, width, index) in line_hits {
+                let area = Rect::new(line_layout.x + column, line_layout.y + row as u16, width, 1)
+                    .intersection(line_layout);
+                hits.push((area, index));
+            }
+        }
+    }
+    hits
+}
+
+fn pipeline_status_style(status: &PipelineStatusEnum) -> Style {
+    match status {
+        PipelineStatusEnum::SUCCESS => theme::Catpuccin::green(),
+        PipelineStatusEnum::FAILED => theme::Catpuccin::red(),
+        PipelineStatusEnum::RUNNING => theme::Catpuccin::blue(),
+        _ => theme::Catpuccin::text(),
+    }
+}
+
+/// Jobs on a line as their column, width and index among the visible jobs.
+type JobHits = Vec<(u16, u16, usize)>;
+
+/// The downstream pipeline triggered by `job` as a header line with its
+/// overall status, followed by its stages (and their downstream pipelines) if
+/// expanded. `next` is the index of the first job of the pipeline among the
+/// visible jobs and is moved past its jobs.
+fn downstream_lines(
+    job: &Job,
+    downstreams: &HashMap<String, Option<&Project>>,
+    selected: usize,
+    next: &mut usize,
+    depth: usize,
+) -> Vec<(Line<'static>, JobHits)> {
+    let Some(downstream) = &job.downstream else {
+        return Vec::new();
+    };
+    let indent = "  ".repeat(depth);
+    let expanded = downstreams.get(&job.id);
+    let marker = if expanded.is_some() { "▾" } else { "▸" };
+    let header = format!("{}{} {} #{}", indent, marker, downstream.project, downstream.iid);
+
+    let mut header = vec![
+        Span::styled(header, theme::Catpuccin::text()),
+        Span::styled(
+            format!("  {:?}", downstream.status).to_lowercase(),
+            pipeline_status_style(&downstream.status),
+        ),
+    ];
+    let pipeline = match expanded {
+        None => return vec![(Line::from(header), Vec::new())],
+        Some(project) => project.and_then(|p| p.pipelines.first()),
+    };
+    let Some(pipeline) = pipeline else {
+        header.push(Span::styled(
+            "  loading ...",
+            theme::Catpuccin::text().add_modifier(Modifier::DIM),
+        ));
+        return vec![(Line::from(header), Vec::new())];
+    };
+
+    let mut lines = vec![(Line::from(header), Vec::new())];
+    for stage in &pipeline.stages {
+        let prefix = Span::styled(
+            format!("{}  {}  ", indent, stage.name),
+            theme::Catpuccin::text(),
+        );
+        let mut column = prefix.width() as u16;
+        let mut spans = vec![prefix];
+        let mut hits = Vec::new();
+        for job in &stage.jobs {
+            let span = match *next == selected {
+                true => job_span(job).reversed(),
+                false => job_span(job),
+            };
+            let width = span.width() as u16;
+            hits.push((column, width, *next));
+            column += width;
+            spans.push(span);
+            *next += 1;
+        }
+        lines.push((Line::from(spans), hits));
+
+        for job in &stage.jobs {
+            lines.extend(downstream_lines(job, downstreams, selected, next, depth + 1));
+        }
+    }
+    lines
+}
+
+/// What the symbols of the jobs mean.
+fn legend() -> Line<'static> {
+    let symbols = [
+        (CiJobStatus::SUCCESS, "success"),
+        (CiJobStatus::FAILED, "failed"),
+        (CiJobStatus::CREATED, "created"),
+        (CiJobStatus::RUNNING, "running or waiting"),
+        (CiJobStatus::SKIPPED, "skipped"),
+    ];
+    let mut spans = Vec::new();
+    for (status, label) in symbols {
+        let symbol = status_span(&status);
+        spans.push(Span::styled(symbol.content.trim().to_string(), symbol.style));
+        spans.push(Span::styled(format!(" {}  ", label), theme::Catpuccin::text()));
+    }
+    spans.push(Span::styled(
+        format!("▸ downstream ({})", crate::keymap::current().hint(Action::Toggle)),
+        theme::Catpuccin::text(),
+    ));
+    Line::from(spans)
+}
+
+fn job_span(job: &Job) -> Span<'static> {
+    status_span(&job.status)
+}
+
+fn status_span(status: &CiJobStatus) -> Span<'static> {
+    match status {
+        CiJobStatus::FAILED => Span::styled("⬤  ", theme::Catpuccin::red()),
+        CiJobStatus::SUCCESS => Span::styled("⬤  ", theme::Catpuccin::green()),
+        CiJobStatus::CREATED => Span::styled("⬤  ", theme::Catpuccin::blue()),
+        CiJobStatus::SKIPPED => Span::styled("  »  ", theme::Catpuccin::text()),
+        _ => Span::styled("⬤  ", theme::Catpuccin::text()),
+    }
+}
+
+/// `name  duration  ~median` of a job which has (at least) started;
+/// jobs running much longer than usual are highlighted.
+fn duration_line(job: &Job, median: Option<i64>) -> Option<Line<'static>> {
+    let elapsed = job.elapsed()?;
+
+    let mut spans = vec![
+        job_span(job),
+        Span::styled(job.name.clone(), theme::Catpuccin::text()),
+        Span::styled(format!("  {}", humanize::duration(elapsed)), theme::Catpuccin::text()),
+    ];
+    if let Some(median) = median {
+        spans.push(Span::styled(
+            format!("  ~{}", humanize::duration(median)),
+            theme::Catpuccin::text().add_modifier(Modifier::DIM),
+        ));
+        if median > 0 && elapsed as f64 >= median as f64 * SLOW_FACTOR {
+            spans.push(Span::styled(
+                format!("  {:.1}x slower", elapsed as f64 / median as f64),
+                theme::Catpuccin::red(),
+            ));
+        }
+    }
+    Some(Line::from(spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    async fn fetch(params: JobQueryParams) -> Project {
+        let (_controller, schedule) = crate::fetchers::poll::control();
+        crate::fetchers::jobs_pipelines(testing::fixtures(), params, schedule)
+            .recv()
+            .await
+            .expect("an update")
+            .expect("an existing project")
+    }
+
+    #[tokio::test]
+    async fn renders_stages_and_jobs() {
+        let project = fetch(JobQueryParams::new("group/project".to_string()).with_count(1)).await;
+        let medians = HashMap::from([("compile".to_string(), 90), ("unit".to_string(), 100)]);
+        let mut terminal = testing::terminal(80, 16);
+        terminal
+            .draw(|frame| {
+                render(frame, &project, &medians, 1, &HashMap::new(), None, false, None, None);
+            })
+            .expect("draw");
+
+        testing::assert_snapshot("pipeline_jobs", terminal.backend().buffer());
+    }
+
+    #[tokio::test]
+    async fn renders_expanded_downstream() {
+        let project = fetch(JobQueryParams::new("group/project".to_string()).with_count(1)).await;
+        let docs = JobQueryParams::new("group/docs".to_string()).with_pipeline("7".to_string());
+        let docs = fetch(docs).await;
+        let expanded = HashMap::from([("gid://gitlab/Ci::Bridge/2004".to_string(), Some(&docs))]);
+        let mut terminal = testing::terminal(80, 18);
+        terminal
+            .draw(|frame| {
+                render(frame, &project, &HashMap::new(), 3, &expanded, Some(0), true, None, None);
+            })
+            .expect("draw");
+
+        testing::assert_snapshot("pipeline_downstream", terminal.backend().buffer());
+    }
+
+    #[tokio::test]
+    async fn renders_missing_pipeline() {
+        let mut project =
+            fetch(JobQueryParams::new("group/project".to_string()).with_count(1)).await;
+        project.pipelines.clear();
+        let none = HashMap::new();
+        let mut terminal = testing::terminal(40, 4);
+        terminal
+            .draw(|frame| {
+                render(frame, &project, &HashMap::new(), 0, &none, None, false, None, None);
+            })
+            .expect("draw");
 
-        let line = Line::from_iter(stage.jobs.iter().map(|j| match j.status {
-            CiJobStatus::FAILED => Span::styled("⬤  ", theme::Catpuccin::red()),
-            CiJobStatus::SUCCESS => Span::styled("⬤  ", theme::Catpuccin::green()),
-            CiJobStatus::CREATED => Span::styled("⬤  ", theme::Catpuccin::blue()),
-            CiJobStatus::SKIPPED => Span::styled("  »  ", theme::Catpuccin::text()),
-            _ => Span::styled("⬤  ", theme::Catpuccin::text()),
-        }));
-        frame.render_widget(line, line_layout);
+        testing::assert_snapshot("pipeline_none", terminal.backend().buffer());
     }
 }
@@ -1,77 +1,340 @@
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Padding, Paragraph};
+use ratatui::widgets::{Bar, BarChart, BarGroup, Padding, Paragraph};
 use ratatui::Frame;
 use std::io;
 
 use clap::Args;
 
+use crate::cmds::Failure;
 use crate::events::*;
 use crate::fetchers::pipelines::BranchPipelineUpdate;
 use crate::fetchers::pipelines::PipelineStatusEnum;
+use crate::fetchers::pipelines::PipelineSummary;
 use crate::fetchers::pipelines::PipelinesQueryArgs;
+use crate::fetchers::poll::Controller;
+use crate::fetchers::projects::{FetchError, NotFound};
+use crate::fetchers::trigger::TriggerArgs;
+use crate::fetchers::JobQueryParams;
 use crate::gitlab_ref::*;
+use crate::humanize;
+use crate::prompt::{Input, Prompt};
 use crate::theme;
 use crate::theme::Theme;
 
 #[derive(Debug, Args)]
 pub struct PipelinesArgs {
-    gitlab_ref: GitlabRef,
+    /// `-` for the ref watched last. Defaults to the project and branch of the
+    /// git checkout in the current directory; outside of one a picker offers
+    /// the recent refs and your projects
+    gitlab_ref: Option<GitlabRef>,
+    /// Show a chart of the pipeline durations below the pipelines
+    #[arg(long)]
+    durations: bool,
+    /// Stop fetching jobs of a pipeline after this many
+    #[arg(long, default_value_t = crate::fetchers::JOB_LIMIT)]
+    max_jobs: usize,
 }
 
 struct App {
-    receiver: tokio::sync::mpsc::Receiver<BranchPipelineUpdate>,
+    /// `None` when replaying a recorded session
+    gapi: Option<gitlab::AsyncGitlab>,
+    /// the watched ref, shown until the first update arrives
+    title: String,
+    receiver: tokio::sync::mpsc::Receiver<Result<BranchPipelineUpdate, FetchError>>,
+    controller: Controller,
     project: Option<BranchPipelineUpdate>,
+    /// the project does not exist or is not accessible
+    missing: Option<NotFound>,
+    /// the last request failed, the pipelines are fetched again on the next poll
+    error: Option<String>,
+    /// keeps the last update for the next start
+    cache: crate::cache::File,
+    /// the project is the cached one, no update was received yet
+    stale: bool,
+    /// when the last update was fetched
+    updated: Option<chrono::DateTime<chrono::Utc>>,
+    /// index into the pipelines of the project; 0 is the most recent one
+    selected: usize,
+    durations: bool,
+    /// shows what the status symbols mean
+    legend: bool,
+    /// outcome of the last action like running a pipeline
+    status: Option<String>,
+    /// asks for the variables of a pipeline to run
+    prompt: Option<Prompt>,
+    /// the pipeline being created, its params to open it with once it is
+    created_sender: tokio::sync::mpsc::Sender<Result<JobQueryParams, String>>,
+    created_receiver: tokio::sync::mpsc::Receiver<Result<JobQueryParams, String>>,
+    /// where the pipelines were drawn, for the mouse
+    hits: Vec<(Rect, usize)>,
 }
 
 impl App {
-    fn new(gapi: gitlab::AsyncGitlab, args: &PipelinesArgs) -> Self {
-        let receiver = match &args.gitlab_ref {
-            GitlabRef::Repo(repo) => crate::fetchers::branch_pipelines(
-                gapi.clone(),
-                PipelinesQueryArgs::new(repo.clone()).with_count(30),
-            ),
-
-            GitlabRef::Branch(repo, branch) => crate::fetchers::branch_pipelines(
-                gapi.clone(),
-                PipelinesQueryArgs::new(repo.clone())
-                    .with_reference(branch.clone())
-                    .with_count(30),
-            ),
+    fn new(gapi: Option<gitlab::AsyncGitlab>, gitlab_ref: &GitlabRef, durations: bool) -> Self {
+        let params = match gitlab_ref {
+            GitlabRef::Repo(repo) => PipelinesQueryArgs::new(repo.clone()).with_count(30),
+            GitlabRef::Branch(repo, branch) => PipelinesQueryArgs::new(repo.clone())
+                .with_reference(branch.clone())
+                .with_count(30),
         };
+        let (controller, schedule) = crate::fetchers::poll::control();
+        let receiver = crate::session::updates("pipelines", &params, || {
+            let gapi = gapi.clone().expect("gitlab outside of a replay");
+            crate::fetchers::branch_pipelines(gapi, params.clone(), schedule)
+        });
+        let cache = crate::cache::File::new("pipelines", &params);
+        let project: Option<BranchPipelineUpdate> = cache.load();
+        let (created_sender, created_receiver) = tokio::sync::mpsc::channel(1);
 
         App {
+            gapi,
+            title: gitlab_ref.to_string(),
             receiver,
-            project: None,
+            controller,
+            stale: project.is_some(),
+            project,
+            missing: None,
+            error: None,
+            cache,
+            updated: None,
+            selected: 0,
+            durations,
+            legend: false,
+            status: None,
+            prompt: None,
+            created_sender,
+            created_receiver,
+            hits: Vec::new(),
         }
     }
 
     fn update(&mut self) {
         // check if there is a new project update
-        if let Ok(p) = self.receiver.try_recv() {
-            self.project = Some(p);
+        let p = match self.receiver.try_recv() {
+            Ok(Ok(p)) => p,
+            Ok(Err(FetchError::NotFound(missing))) => {
+                self.missing = Some(missing);
+                return;
+            }
+            Ok(Err(FetchError::Transient(err))) => {
+                self.error = Some(err);
+                return;
+            }
+            Err(_) => return,
+        };
+
+        // keep the selection on the same pipeline when new ones show up
+        let selected_id = self.selected_pipeline().map(|s| s.id.clone());
+        self.selected = selected_id
+            .and_then(|id| p.pipelines.iter().position(|s| s.id == id))
+            .unwrap_or(0);
+        self.cache.keep(&p);
+        self.updated = Some(p.fetched_at);
+        self.project = Some(p);
+        self.stale = false;
+        self.error = None;
+    }
+
+    fn selected_pipeline(&self) -> Option<&PipelineSummary> {
+        self.project
+            .as_ref()
+            .and_then(|p| p.pipelines.get(self.selected))
+    }
+
+    fn select_next(&mut self) {
+        let count = self.project.as_ref().map_or(0, |p| p.pipelines.len());
+        if self.selected + 1 < count {
+            self.selected += 1;
+        }
+    }
+
+    fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Params to open the selected pipeline with.
+    fn open_selected(&self) -> Option<JobQueryParams> {
+        let project = self.project.as_ref()?;
+        let selected = self.selected_pipeline()?;
+        Some(
+            JobQueryParams::new(project.project.clone())
+                .with_pipeline(selected.iid.clone()),
+        )
+    }
+
+    /// The ref a new pipeline is run for: the watched one or, if no ref is
+    /// watched, the one of the selected pipeline.
+    fn run_ref(&self) -> Option<(String, String)> {
+        let project = self.project.as_ref()?;
+        let git_ref = project
+            .branch
+            .clone()
+            .or_else(|| self.selected_pipeline().map(|p| p.git_ref.clone()))?;
+        Some((project.project.clone(), git_ref))
+    }
+
+    /// Asks for the variables of a new pipeline.
+    fn ask_variables(&mut self) {
+        if self.gapi.is_none() {
+            self.status = Some("running pipelines is not possible in a replay".to_string());
+            return;
+        }
+        if self.run_ref().is_some() {
+            self.prompt = Some(Prompt::new("variables (KEY=VALUE ...)", String::new()));
         }
     }
 
-    fn render(&self, frame: &mut Frame) {
-        if let Some(p) = &self.project {
-            render(frame, p);
+    /// Passes a key on to the prompt, runs the pipeline once it is confirmed.
+    fn answer(&mut self, key: &KeyEvent) {
+        let Some(prompt) = &mut self.prompt else {
+            return;
+        };
+        match prompt.key(key) {
+            Input::Editing => {}
+            Input::Canceled => self.prompt = None,
+            Input::Done(variables) => {
+                self.prompt = None;
+                let variables: Result<Vec<_>, _> = variables
+                    .split_whitespace()
+                    .map(crate::cmds::run::parse_key_value)
+                    .collect();
+                match variables {
+                    Ok(variables) => self.run_pipeline(variables),
+                    Err(err) => self.status = Some(err),
+                }
+            }
         }
     }
+
+    /// Creates a new pipeline in the background, see [`App::created`].
+    fn run_pipeline(&mut self, variables: Vec<(String, String)>) {
+        let (Some(gapi), Some((project, git_ref))) = (self.gapi.clone(), self.run_ref()) else {
+            return;
+        };
+
+        self.status = Some(format!("running pipeline for {} ...", git_ref));
+        let params = TriggerArgs::new(project.clone(), git_ref).with_variables(variables);
+        let sender = self.created_sender.clone();
+        tokio::spawn(async move {
+            let created = match crate::fetchers::trigger::create_pipeline(&gapi, &params).await {
+                Ok(created) => {
+                    Ok(JobQueryParams::new(project).with_pipeline(created.iid.to_string()))
+                }
+                Err(err) => Err(format!("running pipeline failed: {}", err)),
+            };
+            _ = sender.send(created).await;
+        });
+    }
+
+    /// The params to open the pipeline with once it was created.
+    fn created(&mut self) -> Option<JobQueryParams> {
+        match self.created_receiver.try_recv().ok()? {
+            Ok(params) => {
+                self.status = None;
+                Some(params)
+            }
+            Err(err) => {
+                self.status = Some(err);
+                None
+            }
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        if let Some(missing) = &self.missing {
+            render_not_found(frame, frame.area(), missing);
+            return;
+        }
+        let Some(p) = &self.project else {
+            let mut loading = theme::Catpuccin.loading(self.title.clone());
+            if let Some(err) = &self.error {
+                loading = loading.title_bottom(Line::styled(err.clone(), theme::Catpuccin::red()));
+            }
+            frame.render_widget(loading, frame.area());
+            return;
+        };
+        let updated = match self.stale {
+            true => Some(humanize::STALE.to_string()),
+            false => self
+                .updated
+                .map(|u| humanize::updated(u, self.controller.paused())),
+        };
+        let status = match (&self.prompt, &self.status, &self.error) {
+            (Some(prompt), _, _) => Some(prompt.line().left_aligned()),
+            (None, Some(status), _) => Some(Line::from(status.clone())),
+            (None, None, Some(err)) => Some(Line::styled(err.clone(), theme::Catpuccin::red())),
+            (None, None, None) => None,
+        };
+        self.hits = render(
+            frame,
+            p,
+            self.selected,
+            self.durations,
+            self.legend,
+            status,
+            updated.as_deref(),
+        );
+    }
+}
+
+pub async fn run(gapi: Option<gitlab::AsyncGitlab>, args: &PipelinesArgs) -> Result<(), Failure> {
+    let mut event_handler = EventHandler::new(250);
+    let gitlab_ref = match (&args.gitlab_ref, &gapi) {
+        (Some(gitlab_ref), _) => gitlab_ref.clone(),
+        (None, Some(gapi)) => match GitlabRef::from_checkout() {
+            Some(gitlab_ref) => gitlab_ref,
+            None => match crate::cmds::pick::pick_ref(gapi, &mut event_handler).await {
+                Some(gitlab_ref) => gitlab_ref,
+                None => return Ok(()),
+            },
+        },
+        (None, None) => {
+            return Err(Failure::usage(
+                "a replay requires the ref like path/to/project@main",
+            ))
+        }
+    };
+    let gitlab_ref = crate::recent::watch(&gitlab_ref).map_err(Failure::usage)?;
+
+    show(gapi, gitlab_ref, args.durations, args.max_jobs, &mut event_handler).await;
+    Ok(())
 }
 
-pub async fn run(gapi: gitlab::AsyncGitlab, args: &PipelinesArgs) {
+/// Live view of the pipelines of `gitlab_ref`, returns once the user quits.
+/// The pipelines opened from it fetch up to `max_jobs` jobs.
+pub async fn show(
+    gapi: Option<gitlab::AsyncGitlab>,
+    gitlab_ref: GitlabRef,
+    durations: bool,
+    max_jobs: usize,
+    event_handler: &mut EventHandler,
+) {
+    let mut app = App::new(gapi.clone(), &gitlab_ref, durations);
+
+    while let Some(params) = select(&mut app, event_handler).await {
+        let params = params.with_job_limit(max_jobs);
+        crate::cmds::pipeline::show(gapi.clone(), params, event_handler).await;
+    }
+}
+
+/// Shows the pipeline strip until the user either quits (`None`), opens one
+/// of the pipelines or runs a new one.
+async fn select(app: &mut App, event_handler: &mut EventHandler) -> Option<JobQueryParams> {
     let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
     ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
-    let viewport = ratatui::Viewport::Inline(5);
+    let viewport = match app.durations {
+        true => ratatui::Viewport::Inline(12),
+        false => ratatui::Viewport::Inline(6),
+    };
     let mut terminal =
         ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
             .expect("terminal setup to work");
 
-    let mut app = App::new(gapi, args);
-    let mut event_handler = EventHandler::new(250);
-
-    loop {
+    let params = loop {
         app.update();
+        if let Some(params) = app.created() {
+            break Some(params);
+        }
         // ignoring all errors
         terminal
             .draw(|frame| app.render(frame))
@@ -79,48 +342,309 @@ pub async fn run(gapi: gitlab::AsyncGitlab, args: &PipelinesArgs) {
 
         match event_handler.next().await {
             Event::Tick => {}
-            Event::Quit => break,
-            Event::Key(_) => break,
-            Event::Resize(_, _) => terminal.autoresize().expect("failed to resize"),
+            Event::Quit => break None,
+            Event::Action(action) => match action {
+                Action::Quit => break None,
+                Action::Refresh => app.controller.refresh(),
+                Action::Pause => app.controller.toggle_pause(),
+                Action::Prev => app.select_prev(),
+                Action::Next => app.select_next(),
+                Action::Open => {
+                    if let Some(params) = app.open_selected() {
+                        break Some(params);
+                    }
+                }
+                Action::Run => app.ask_variables(),
+                Action::ToggleLegend => app.legend = !app.legend,
+                _ => {}
+            },
+            Event::Key(key) => app.answer(&key),
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let Some(index) = hit(&app.hits, &mouse) else {
+                        continue;
+                    };
+                    app.selected = index;
+                    if let Some(params) = app.open_selected() {
+                        break Some(params);
+                    }
+                }
+                MouseEventKind::ScrollUp => app.select_prev(),
+                MouseEventKind::ScrollDown => app.select_next(),
+                _ => {}
+            },
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
         }
+        event_handler.text_input(app.prompt.is_some());
+    };
+    app.prompt = None;
+    event_handler.text_input(false);
+    if params.is_some() {
+        terminal.clear().expect("failed to clear terminal");
     }
+    params
 }
 
-fn render(frame: &mut Frame, project: &BranchPipelineUpdate) {
-    let project_block = theme::Catpuccin.block().title(project.project.clone());
-    frame.render_widget(&project_block, frame.area());
+fn status_span(status: &PipelineStatusEnum) -> Span<'static> {
+    let pipeline_block = " ███ ";
+    match status {
+        PipelineStatusEnum::SUCCESS => Span::styled(pipeline_block, theme::Catpuccin::green()),
+        PipelineStatusEnum::FAILED => Span::styled(pipeline_block, theme::Catpuccin::red()),
+        PipelineStatusEnum::CREATED => Span::styled(pipeline_block, theme::Catpuccin::text()),
+        PipelineStatusEnum::RUNNING => Span::styled(pipeline_block, theme::Catpuccin::blue()),
+        PipelineStatusEnum::SKIPPED => Span::styled("  »  ", theme::Catpuccin::text()),
+        PipelineStatusEnum::CANCELED => Span::from("  ☠  "),
+        PipelineStatusEnum::MANUAL => Span::from("  👋  "),
+        PipelineStatusEnum::SCHEDULED => Span::from("  🕔 "),
+        PipelineStatusEnum::PENDING => Span::styled(pipeline_block, Modifier::DIM),
+        PipelineStatusEnum::CANCELING => Span::styled(pipeline_block, Modifier::DIM),
+        PipelineStatusEnum::PREPARING => Span::styled(pipeline_block, Modifier::DIM),
+        PipelineStatusEnum::WAITING_FOR_RESOURCE => Span::styled(pipeline_block, Modifier::DIM),
+        PipelineStatusEnum::WAITING_FOR_CALLBACK => Span::styled(pipeline_block, Modifier::DIM),
+        PipelineStatusEnum::Other(_) => Span::styled(pipeline_block, Modifier::DIM),
+    }
+}
 
-    let project_content_area = project_block.inner(frame.area());
+pub(crate) fn status_style(status: &PipelineStatusEnum) -> Style {
+    match status {
+        PipelineStatusEnum::SUCCESS => theme::Catpuccin::green(),
+        PipelineStatusEnum::FAILED => theme::Catpuccin::red(),
+        PipelineStatusEnum::RUNNING => theme::Catpuccin::blue(),
+        _ => theme::Catpuccin::text().add_modifier(Modifier::DIM),
+    }
+}
 
-    let pipeline_block = " ███ ";
-    let line: Line = project
-        .states
+/// What the symbols of the pipeline strip mean.
+pub(crate) fn legend() -> Line<'static> {
+    let statuses = [
+        (PipelineStatusEnum::SUCCESS, "success"),
+        (PipelineStatusEnum::FAILED, "failed"),
+        (PipelineStatusEnum::RUNNING, "running"),
+        (PipelineStatusEnum::PENDING, "pending"),
+        (PipelineStatusEnum::SKIPPED, "skipped"),
+        (PipelineStatusEnum::CANCELED, "canceled"),
+        (PipelineStatusEnum::MANUAL, "manual"),
+    ];
+    let mut spans = Vec::new();
+    for (status, label) in statuses {
+        let symbol = status_span(&status);
+        spans.push(Span::styled(symbol.content.trim().replace("███", "█"), symbol.style));
+        spans.push(Span::styled(format!(" {}  ", label), theme::Catpuccin::text()));
+    }
+    Line::from(spans).centered()
+}
+
+/// A single character for the status, for places without room for a block.
+pub(crate) fn status_glyph(status: &PipelineStatusEnum) -> &'static str {
+    match status {
+        PipelineStatusEnum::SUCCESS => "✔",
+        PipelineStatusEnum::FAILED => "✘",
+        PipelineStatusEnum::RUNNING => "●",
+        PipelineStatusEnum::SKIPPED => "»",
+        PipelineStatusEnum::CANCELED => "☠",
+        PipelineStatusEnum::MANUAL => "👋",
+        PipelineStatusEnum::SCHEDULED => "🕔",
+        _ => "◌",
+    }
+}
+
+fn render(
+    frame: &mut Frame,
+    project: &BranchPipelineUpdate,
+    selected: usize,
+    durations: bool,
+    legend: bool,
+    status: Option<Line<'static>>,
+    updated: Option<&str>,
+) -> Vec<(Rect, usize)> {
+    let area = frame.area();
+    render_in(frame, area, project, selected, durations, legend, status, updated)
+}
+
+/// Replaces the view of a project which does not exist or is not accessible.
+pub(crate) fn render_not_found(frame: &mut Frame, area: Rect, missing: &NotFound) {
+    let block = theme::Catpuccin.block().title(missing.project.clone());
+    let mut lines = vec![Line::styled(
+        "not found or not accessible with this token",
+        theme::Catpuccin::red(),
+    )];
+    if !missing.suggestions.is_empty() {
+        lines.push(Line::from(format!("did you mean {}?", missing.suggestions.join(" or "))));
+    }
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// The pipeline strip of `project` within `area`; nothing is highlighted if
+/// `selected` is out of range. The `legend` takes the place of the details of
+/// the selected pipeline. Returns the area of every pipeline block.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_in(
+    frame: &mut Frame,
+    area: Rect,
+    project: &BranchPipelineUpdate,
+    selected: usize,
+    durations: bool,
+    legend: bool,
+    status: Option<Line<'static>>,
+    updated: Option<&str>,
+) -> Vec<(Rect, usize)> {
+    let mut project_block = theme::Catpuccin.block().title(project.project.clone());
+    if let Some(updated) = updated {
+        project_block = project_block.title(Line::from(updated.to_string()).right_aligned());
+    }
+    if let Some(status) = status {
+        let status = match status.alignment {
+            Some(_) => status,
+            None => status.right_aligned(),
+        };
+        project_block = project_block.title_bottom(status);
+    }
+    frame.render_widget(&project_block, area);
+
+    let [strip_area, detail_area, chart_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(project_block.inner(area));
+
+    let spans: Vec<Span> = project
+        .pipelines
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            if i == selected {
+                status_span(&p.status).reversed()
+            } else {
+                status_span(&p.status)
+            }
+        })
+        .collect();
+
+    let strip_block = theme::Catpuccin
+        .block()
+        .padding(Padding::horizontal(3))
+        .title(project.branch.as_ref().unwrap_or(&"".to_string()).clone());
+    let hits = span_areas(&spans, strip_block.inner(strip_area));
+    let paragraph = Paragraph::new(Line::from(spans))
+        .centered()
+        .block(strip_block);
+
+    frame.render_widget(paragraph, strip_area);
+
+    if legend {
+        frame.render_widget(self::legend(), detail_area);
+    } else if let Some(p) = project.pipelines.get(selected) {
+        let age = chrono::Utc::now().signed_duration_since(p.created_at);
+        let detail = Line::from(format!(
+            "#{} ({})  {}  {}  by {}  {} ago",
+            p.iid,
+            p.id.rsplit('/').next().unwrap_or(&p.id),
+            p.git_ref,
+            p.sha.as_deref().map_or("-".to_string(), |s| s.chars().take(8).collect()),
+            p.author,
+            humanize::age(age),
+        ))
+        .style(theme::Catpuccin::text())
+        .centered();
+        frame.render_widget(detail, detail_area);
+    }
+
+    if durations {
+        render_durations(frame, project, chart_area);
+    }
+    hits
+}
+
+/// Where the spans of a centered line end up within `area`.
+fn span_areas(spans: &[Span], area: Rect) -> Vec<(Rect, usize)> {
+    let width: u16 = spans.iter().map(|s| s.width() as u16).sum();
+    let mut x = area.x + area.width.saturating_sub(width) / 2;
+    spans
         .iter()
-        .map(|ok| match ok {
-            PipelineStatusEnum::SUCCESS => Span::styled(pipeline_block, theme::Catpuccin::green()),
-            PipelineStatusEnum::FAILED => Span::styled(pipeline_block, theme::Catpuccin::red()),
-            PipelineStatusEnum::CREATED => Span::styled(pipeline_block, theme::Catpuccin::text()),
-            PipelineStatusEnum::RUNNING => Span::styled(pipeline_block, theme::Catpuccin::blue()),
-            PipelineStatusEnum::SKIPPED => Span::styled("  »  ", theme::Catpuccin::text()),
-            PipelineStatusEnum::CANCELED => Span::from("  ☠  "),
-            PipelineStatusEnum::MANUAL => Span::from("  👋  "),
-            PipelineStatusEnum::SCHEDULED => Span::from("  🕔 "),
-            PipelineStatusEnum::PENDING => Span::styled(pipeline_block, Modifier::DIM),
-            PipelineStatusEnum::CANCELING => Span::styled(pipeline_block, Modifier::DIM),
-            PipelineStatusEnum::PREPARING => Span::styled(pipeline_block, Modifier::DIM),
-            PipelineStatusEnum::WAITING_FOR_RESOURCE => Span::styled(pipeline_block, Modifier::DIM),
-            PipelineStatusEnum::WAITING_FOR_CALLBACK => Span::styled(pipeline_block, Modifier::DIM),
-            PipelineStatusEnum::Other(_) => Span::styled(pipeline_block, Modifier::DIM),
+        .enumerate()
+        .map(|(i, span)| {
+            let span_area = Rect::new(x, area.y, span.width() as u16, 1).intersection(area);
+            x += span.width() as u16;
+            (span_area, i)
         })
-        .collect::<Vec<Span>>()
-        .into();
+        .collect()
+}
+
+/// Bar chart of the pipeline durations, each bar is placed below its pipeline block.
+fn render_durations(frame: &mut Frame, project: &BranchPipelineUpdate, area: Rect) {
+    let bars: Vec<Bar> = project
+        .pipelines
+        .iter()
+        .map(|p| {
+            Bar::default()
+                .value(p.duration.unwrap_or(0).max(0) as u64)
+                .text_value(String::new())
+                .style(status_style(&p.status))
+        })
+        .collect();
+
+    // the strip is centered and every pipeline block is 5 cells wide with the
+    // block itself in the middle 3 cells
+    let width = u16::try_from(bars.len() * 5).unwrap_or(u16::MAX).min(area.width);
+    let [chart_area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let chart_area = chart_area.inner(Margin::new(1, 0));
 
-    let paragraph = Paragraph::new(line).centered().block(
-        theme::Catpuccin
-            .block()
-            .padding(Padding::horizontal(3))
-            .title(project.branch.as_ref().unwrap_or(&"".to_string()).clone()),
-    );
+    let chart = BarChart::default()
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(3)
+        .bar_gap(2);
+    frame.render_widget(chart, chart_area);
+}
 
-    frame.render_widget(paragraph, project_content_area);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    async fn project() -> BranchPipelineUpdate {
+        let params = PipelinesQueryArgs::new("group/project".to_string())
+            .with_reference("main".to_string())
+            .with_count(30);
+        let (_controller, schedule) = crate::fetchers::poll::control();
+        let mut project = crate::fetchers::branch_pipelines(testing::fixtures(), params, schedule)
+            .recv()
+            .await
+            .expect("an update")
+            .expect("an existing project");
+        // the age of a pipeline is shown relative to now
+        for (i, p) in project.pipelines.iter_mut().enumerate() {
+            p.created_at = chrono::Utc::now() - chrono::TimeDelta::hours(i as i64 + 1);
+        }
+        project
+    }
+
+    #[tokio::test]
+    async fn renders_strip() {
+        let project = project().await;
+        let mut terminal = testing::terminal(80, 6);
+        terminal
+            .draw(|frame| {
+                render(frame, &project, 1, false, false, None, None);
+            })
+            .expect("draw");
+
+        testing::assert_snapshot("pipelines_strip", terminal.backend().buffer());
+    }
+
+    #[tokio::test]
+    async fn renders_durations_and_status() {
+        let project = project().await;
+        let mut terminal = testing::terminal(80, 12);
+        terminal
+            .draw(|frame| {
+                let status = Line::from("running pipeline failed");
+                render(frame, &project, 0, true, true, Some(status), None);
+            })
+            .expect("draw");
+
+        testing::assert_snapshot("pipelines_durations", terminal.backend().buffer());
+    }
 }
@@ -0,0 +1,53 @@
+use clap::Args;
+
+use crate::cmds::Failure;
+use crate::events::*;
+use crate::fetchers::trigger::TriggerArgs;
+use crate::fetchers::JobQueryParams;
+use crate::gitlab_ref::*;
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// The pipeline is created for this ref, `-` for the ref watched last
+    gitlab_ref: GitlabRef,
+    /// CI variable passed to the pipeline, can be given multiple times
+    #[arg(short = 'v', long = "variable", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    variables: Vec<(String, String)>,
+    /// Input passed to the pipeline, can be given multiple times
+    #[arg(short = 'i', long = "input", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    inputs: Vec<(String, String)>,
+    /// Stop fetching jobs of a pipeline after this many
+    #[arg(long, default_value_t = crate::fetchers::JOB_LIMIT)]
+    max_jobs: usize,
+}
+
+pub(crate) fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE but got `{}`", s)),
+    }
+}
+
+pub async fn run(gapi: gitlab::AsyncGitlab, args: &RunArgs) -> Result<(), Failure> {
+    let gitlab_ref = crate::recent::watch(&args.gitlab_ref).map_err(Failure::usage)?;
+    let GitlabRef::Branch(project, git_ref) = &gitlab_ref else {
+        return Err(Failure::usage(
+            "running a pipeline requires a ref like path/to/project@main",
+        ));
+    };
+
+    let params = TriggerArgs::new(project.clone(), git_ref.clone())
+        .with_variables(args.variables.clone())
+        .with_inputs(args.inputs.clone());
+    let created = crate::fetchers::trigger::create_pipeline(&gapi, &params)
+        .await
+        .map_err(|err| Failure::request(format!("creating the pipeline failed due to: {}", err)))?;
+
+    let params = JobQueryParams::new(project.clone())
+        .with_pipeline(created.iid.to_string())
+        .with_job_limit(args.max_jobs);
+    let mut event_handler = EventHandler::new(250);
+
+    crate::cmds::pipeline::show(Some(gapi), params, &mut event_handler).await;
+    Ok(())
+}
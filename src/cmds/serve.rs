@@ -0,0 +1,39 @@
+use std::net::SocketAddr;
+
+use clap::Args;
+
+use crate::cmds::pipelines::PipelinesArgs;
+use crate::cmds::Failure;
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address the webhooks of GitLab are sent to
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    #[command(flatten)]
+    pipelines: PipelinesArgs,
+}
+
+pub async fn run(
+    gapi: Option<gitlab::AsyncGitlab>,
+    secret: Option<String>,
+    args: &ServeArgs,
+) -> Result<(), Failure> {
+    let Some(secret) = secret.filter(|s| !s.is_empty()) else {
+        return Err(Failure::usage(
+            "serve requires webhook_secret in the config, the secret token of the webhooks",
+        ));
+    };
+    let listener = tokio::net::TcpListener::bind(args.listen)
+        .await
+        .map_err(|err| {
+            Failure::usage(format!(
+                "listening on {} failed due to: {}",
+                args.listen, err
+            ))
+        })?;
+
+    // before any view starts polling
+    crate::webhook::serve(listener, secret);
+    crate::cmds::pipelines::run(gapi, &args.pipelines).await
+}
@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+use std::process;
+
+use chrono::{DateTime, Utc};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+
+use crate::fetchers::history::{CiJobStatus, HistoryQueryArgs, PipelineRun, PipelineStatusEnum};
+use crate::fetchers::projects::FetchError;
+use crate::gitlab_ref::*;
+use crate::humanize;
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// `-` for the ref watched last
+    gitlab_ref: GitlabRef,
+    /// Number of most recent pipelines to take into account
+    #[arg(long, default_value_t = 100)]
+    last: usize,
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Serialize)]
+struct Report {
+    project: String,
+    git_ref: Option<String>,
+    pipelines: usize,
+    succeeded: usize,
+    failed: usize,
+    /// succeeded / (succeeded + failed)
+    success_rate: Option<f64>,
+    duration: Option<DurationStats>,
+    longest_failure_streak: usize,
+    current_failure_streak: usize,
+    flaky_jobs: Vec<FlakyJob>,
+}
+
+#[derive(Serialize)]
+struct DurationStats {
+    mean: f64,
+    p50: i64,
+    p95: i64,
+}
+
+#[derive(Serialize)]
+struct FlakyJob {
+    name: String,
+    /// number of shas the job ran for
+    shas: usize,
+    /// number of shas where the job failed and then passed on retry
+    flaky_shas: usize,
+    flakiness: f64,
+}
+
+pub async fn run(gapi: gitlab::AsyncGitlab, args: &StatsArgs) {
+    let gitlab_ref = match crate::recent::watch(&args.gitlab_ref) {
+        Ok(gitlab_ref) => gitlab_ref,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+    let (project, git_ref) = match &gitlab_ref {
+        GitlabRef::Repo(repo) => (repo.clone(), None),
+        GitlabRef::Branch(repo, branch) => (repo.clone(), Some(branch.clone())),
+    };
+
+    let mut params = HistoryQueryArgs::new(project.clone(), args.last);
+    if let Some(branch) = &git_ref {
+        params = params.with_reference(branch.clone());
+    }
+
+    let runs = match crate::fetchers::history::pipeline_history(&gapi, &params).await {
+        Ok(runs) => runs,
+        Err(err) => {
+            eprintln!("fetching pipelines failed due to: {}", err);
+            process::exit(1);
+        }
+    };
+    if runs.is_empty() {
+        if let Err(err) = crate::fetchers::projects::find_project(&gapi, &project).await {
+            eprintln!("{}", err);
+            process::exit(match err {
+                FetchError::NotFound(_) => 2,
+                FetchError::Transient(_) => 1,
+            });
+        }
+    }
+
+    let report = Report::new(project, git_ref, &runs);
+    match args.format {
+        Format::Table => print_table(&report),
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("report is serializable")
+        ),
+    }
+}
+
+impl Report {
+    /// `runs` are expected to be ordered newest first.
+    fn new(project: String, git_ref: Option<String>, runs: &[PipelineRun]) -> Self {
+        let succeeded = runs
+            .iter()
+            .filter(|r| r.status == PipelineStatusEnum::SUCCESS)
+            .count();
+        let failed = runs
+            .iter()
+            .filter(|r| r.status == PipelineStatusEnum::FAILED)
+            .count();
+
+        let success_rate = match succeeded + failed {
+            0 => None,
+            finished => Some(succeeded as f64 / finished as f64),
+        };
+
+        // only finished pipelines count towards streaks
+        let finished: Vec<bool> = runs
+            .iter()
+            .rev()
+            .filter_map(|r| match r.status {
+                PipelineStatusEnum::SUCCESS => Some(true),
+                PipelineStatusEnum::FAILED => Some(false),
+                _ => None,
+            })
+            .collect();
+        let longest_failure_streak = finished
+            .split(|ok| *ok)
+            .map(|streak| streak.len())
+            .max()
+            .unwrap_or(0);
+        let current_failure_streak = finished.iter().rev().take_while(|ok| !**ok).count();
+
+        Report {
+            project,
+            git_ref,
+            pipelines: runs.len(),
+            succeeded,
+            failed,
+            success_rate,
+            duration: DurationStats::new(runs),
+            longest_failure_streak,
+            current_failure_streak,
+            flaky_jobs: flaky_jobs(runs),
+        }
+    }
+}
+
+impl DurationStats {
+    fn new(runs: &[PipelineRun]) -> Option<Self> {
+        let mut durations: Vec<i64> = runs.iter().filter_map(|r| r.duration).collect();
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort_unstable();
+
+        Some(DurationStats {
+            mean: durations.iter().sum::<i64>() as f64 / durations.len() as f64,
+            p50: percentile(&durations, 50),
+            p95: percentile(&durations, 95),
+        })
+    }
+}
+
+/// Nearest-rank percentile of an already sorted, non empty slice.
+fn percentile(sorted: &[i64], p: usize) -> i64 {
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Runs of a job as (start, succeeded) by job name and sha.
+type Attempts<'a> = BTreeMap<&'a str, BTreeMap<&'a str, Vec<(DateTime<Utc>, bool)>>>;
+
+/// A job is considered flaky for a sha if it failed there and a later run of
+/// it succeeded for the very same sha (usually a retry).
+fn flaky_jobs(runs: &[PipelineRun]) -> Vec<FlakyJob> {
+    let mut attempts: Attempts = BTreeMap::new();
+    for run in runs {
+        for job in &run.jobs {
+            let succeeded = match job.status {
+                CiJobStatus::FAILED => false,
+                CiJobStatus::SUCCESS => true,
+                _ => continue,
+            };
+            // a job that failed before it started is placed at its pipeline
+            let start = job.started_at.unwrap_or(run.created_at);
+            attempts
+                .entry(job.name.as_str())
+                .or_default()
+                .entry(run.sha.as_str())
+                .or_default()
+                .push((start, succeeded));
+        }
+    }
+
+    let mut flaky: Vec<FlakyJob> = attempts
+        .into_iter()
+        .map(|(name, shas)| {
+            let count = shas.len();
+            let flaky_shas = shas.into_values().filter(|runs| recovered(runs)).count();
+            FlakyJob {
+                name: name.to_string(),
+                shas: count,
+                flaky_shas,
+                flakiness: flaky_shas as f64 / count as f64,
+            }
+        })
+        .filter(|j| j.flaky_shas > 0)
+        .collect();
+
+    flaky.sort_by(|a, b| {
+        b.flaky_shas
+            .cmp(&a.flaky_shas)
+            .then(b.flakiness.total_cmp(&a.flakiness))
+    });
+    flaky
+}
+
+/// Whether a failed run is followed by a successful one.
+fn recovered(runs: &[(DateTime<Utc>, bool)]) -> bool {
+    let mut runs = runs.to_vec();
+    runs.sort_by_key(|(start, _)| *start);
+    runs.iter()
+        .skip_while(|(_, succeeded)| *succeeded)
+        .any(|(_, succeeded)| *succeeded)
+}
+
+fn print_table(report: &Report) {
+    let title = match &report.git_ref {
+        Some(r) => format!("{}@{}", report.project, r),
+        None => report.project.clone(),
+    };
+    println!("{}", title);
+    println!("{}", "=".repeat(title.chars().count()));
+    println!("{:<24}{}", "pipelines", report.pipelines);
+    println!("{:<24}{}", "succeeded", report.succeeded);
+    println!("{:<24}{}", "failed", report.failed);
+    match report.success_rate {
+        Some(rate) => println!("{:<24}{:.1}%", "success rate", rate * 100.0),
+        None => println!("{:<24}-", "success rate"),
+    }
+    match &report.duration {
+        Some(d) => {
+            println!("{:<24}{}", "mean duration", humanize::duration(d.mean as i64));
+            println!("{:<24}{}", "p50 duration", humanize::duration(d.p50));
+            println!("{:<24}{}", "p95 duration", humanize::duration(d.p95));
+        }
+        None => println!("{:<24}-", "duration"),
+    }
+    println!("{:<24}{}", "longest failure streak", report.longest_failure_streak);
+    println!("{:<24}{}", "current failure streak", report.current_failure_streak);
+
+    if report.flaky_jobs.is_empty() {
+        println!();
+        println!("no flaky jobs");
+        return;
+    }
+
+    let width = report
+        .flaky_jobs
+        .iter()
+        .map(|j| j.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("job".len());
+    println!();
+    println!("{:<width$}  {:>6}  {:>6}  {:>10}", "job", "flaky", "shas", "flakiness");
+    for job in &report.flaky_jobs {
+        println!(
+            "{:<width$}  {:>6}  {:>6}  {:>9.1}%",
+            job.name,
+            job.flaky_shas,
+            job.shas,
+            job.flakiness * 100.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetchers::history::JobRun;
+
+    fn at(minute: u32) -> DateTime<Utc> {
+        "2024-05-01T10:00:00Z".parse::<DateTime<Utc>>().expect("a time")
+            + chrono::Duration::minutes(minute.into())
+    }
+
+    fn run(sha: &str, jobs: &[(u32, CiJobStatus)]) -> PipelineRun {
+        PipelineRun {
+            sha: sha.to_string(),
+            status: PipelineStatusEnum::SUCCESS,
+            duration: None,
+            created_at: at(0),
+            jobs: jobs
+                .iter()
+                .map(|(minute, status)| JobRun {
+                    name: "test".to_string(),
+                    status: status.clone(),
+                    duration: None,
+                    started_at: Some(at(*minute)),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn flaky_needs_a_success_after_a_failure() {
+        use CiJobStatus::{FAILED, SUCCESS};
+        // newest first like the fetched runs: the retry succeeded
+        let retried = [run("a", &[(5, SUCCESS)]), run("a", &[(1, FAILED)])];
+        let flaky = flaky_jobs(&retried);
+        assert_eq!(flaky.len(), 1);
+        assert_eq!((flaky[0].flaky_shas, flaky[0].shas), (1, 1));
+
+        // broken by a later run of the same sha, not flaky
+        let broken = [run("a", &[(5, FAILED)]), run("a", &[(1, SUCCESS)])];
+        assert!(flaky_jobs(&broken).is_empty());
+
+        // a retry within the same pipeline counts as well
+        let within = [run("b", &[(1, FAILED), (3, SUCCESS)]), run("c", &[(0, SUCCESS)])];
+        let flaky = flaky_jobs(&within);
+        assert_eq!((flaky[0].flaky_shas, flaky[0].shas), (1, 2));
+    }
+}
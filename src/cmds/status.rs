@@ -0,0 +1,256 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::process;
+
+use clap::{Args, ValueEnum};
+
+use crate::cmds::pipelines::{status_glyph, status_style};
+use crate::config::Config;
+use crate::fetchers::pipelines::{PipelineSummary, PipelinesQueryArgs};
+use crate::fetchers::projects::{FetchError, NotFound};
+use crate::gitlab_ref::*;
+use crate::humanize;
+use crate::theme;
+use crate::theme::Theme;
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// `-` for the ref watched last. Defaults to the project and branch of the
+    /// git checkout in the current directory
+    gitlab_ref: Option<GitlabRef>,
+    #[arg(long, value_enum, default_value_t = Format::Plain)]
+    format: Format,
+    /// Seconds the status is answered from the cache instead of GitLab
+    #[arg(long, default_value_t = 30)]
+    max_age: i64,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// `✔ main 4m`
+    Plain,
+    /// colored for the status line of tmux
+    Tmux,
+    /// Pango markup, e.g. for polybar or i3blocks
+    Pango,
+    /// a JSON block of the i3bar protocol
+    I3bar,
+    /// JSON for a custom module of waybar
+    Waybar,
+}
+
+/// The latest pipeline as of `at`.
+#[derive(Serialize, Deserialize)]
+struct Cached {
+    at: chrono::DateTime<chrono::Utc>,
+    pipeline: Option<PipelineSummary>,
+    /// the project does not exist or is not accessible
+    #[serde(default)]
+    missing: Option<NotFound>,
+}
+
+pub async fn run(config: &Config, args: &StatusArgs) {
+    let Some(gitlab_ref) = args.gitlab_ref.clone().or_else(GitlabRef::from_checkout) else {
+        eprintln!("status requires a ref like path/to/project@main outside of a git checkout");
+        process::exit(2);
+    };
+    let gitlab_ref = match crate::recent::watch(&gitlab_ref) {
+        Ok(gitlab_ref) => gitlab_ref,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+    let params = match &gitlab_ref {
+        GitlabRef::Repo(repo) => PipelinesQueryArgs::new(repo.clone()),
+        GitlabRef::Branch(repo, branch) => {
+            PipelinesQueryArgs::new(repo.clone()).with_reference(branch.clone())
+        }
+    }
+    .with_count(1);
+
+    let cache = crate::cache::File::new("status", &params);
+    let max_age = chrono::TimeDelta::seconds(args.max_age);
+    let cached = cache.load::<Cached>();
+    let (cached, stale) = match cached {
+        Some(cached) if chrono::Utc::now() - cached.at < max_age => (cached, false),
+        _ => match fetch(config, params).await {
+            Ok(pipeline) => {
+                let fetched = Cached {
+                    at: chrono::Utc::now(),
+                    pipeline,
+                    missing: None,
+                };
+                cache.store(&fetched);
+                (fetched, false)
+            }
+            // asking again every few seconds does not make it appear
+            Err(FetchError::NotFound(missing)) => {
+                let fetched = Cached {
+                    at: chrono::Utc::now(),
+                    pipeline: None,
+                    missing: Some(missing),
+                };
+                cache.store(&fetched);
+                (fetched, false)
+            }
+            // the last answer is better than none while GitLab is not reachable
+            Err(FetchError::Transient(err)) => {
+                eprintln!("{}", err);
+                let Some(cached) = cached else {
+                    println!("{}", error_line(&gitlab_ref, &err, args.format));
+                    process::exit(1);
+                };
+                (cached, true)
+            }
+        },
+    };
+
+    if let Some(missing) = cached.missing {
+        eprintln!("{}", missing);
+        process::exit(2);
+    }
+    println!(
+        "{}",
+        line(&gitlab_ref, cached.pipeline.as_ref(), stale, args.format)
+    );
+}
+
+async fn fetch(
+    config: &Config,
+    params: PipelinesQueryArgs,
+) -> Result<Option<PipelineSummary>, FetchError> {
+    let gapi = crate::gitlab_api(&config.host, &config.token)
+        .await
+        .map_err(|err| FetchError::Transient(format!("connection to gitlab failed: {}", err)))?;
+    let (_controller, schedule) = crate::fetchers::poll::control();
+    let update = crate::fetchers::branch_pipelines(gapi, params, schedule)
+        .recv()
+        .await
+        .expect("the fetcher sends an update before it stops");
+    Ok(update?.pipelines.into_iter().next())
+}
+
+/// Marks the line of the cached pipeline while GitLab is not reachable.
+const STALE: &str = "(stale)";
+
+/// `stale` if the pipeline is the cached one as fetching a newer one failed.
+fn line(
+    gitlab_ref: &GitlabRef,
+    pipeline: Option<&PipelineSummary>,
+    stale: bool,
+    format: Format,
+) -> String {
+    let Some(pipeline) = pipeline else {
+        let text = format!("– {}", gitlab_ref);
+        return render(
+            format,
+            &text,
+            &theme::Catpuccin::text().fg,
+            "none",
+            "no pipelines",
+        );
+    };
+
+    // the duration of finished pipelines, the time so far of the others
+    let seconds = pipeline
+        .duration
+        .unwrap_or_else(|| (chrono::Utc::now() - pipeline.created_at).num_seconds());
+    let mut text = format!(
+        "{} {} {}",
+        status_glyph(&pipeline.status),
+        pipeline.git_ref,
+        humanize::age(chrono::TimeDelta::seconds(seconds))
+    );
+    let status = format!("{:?}", pipeline.status).to_lowercase();
+    let mut tooltip = format!("{} pipeline #{} {}", gitlab_ref, pipeline.iid, status);
+    if stale {
+        text = format!("{} {}", text, STALE);
+        tooltip = format!("{} {}", tooltip, STALE);
+    }
+    render(
+        format,
+        &text,
+        &status_style(&pipeline.status).fg,
+        &status,
+        &tooltip,
+    )
+}
+
+/// Shown when fetching the pipeline failed and there is no cached one.
+fn error_line(gitlab_ref: &GitlabRef, err: &str, format: Format) -> String {
+    let text = format!("⚠ {}", gitlab_ref);
+    render(format, &text, &theme::Catpuccin::red().fg, "error", err)
+}
+
+fn render(format: Format, text: &str, color: &Option<Color>, class: &str, tooltip: &str) -> String {
+    let color = match color {
+        Some(Color::Rgb(r, g, b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => "#ffffff".to_string(),
+    };
+    match format {
+        Format::Plain => text.to_string(),
+        Format::Tmux => format!("#[fg={}]{}#[default]", color, text.replace('#', "##")),
+        Format::Pango => format!(
+            "<span foreground=\"{}\">{}</span>",
+            color,
+            escape_markup(text)
+        ),
+        Format::I3bar => serde_json::json!({ "full_text": text, "color": color }).to_string(),
+        Format::Waybar => {
+            serde_json::json!({ "text": text, "class": class, "tooltip": tooltip }).to_string()
+        }
+    }
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetchers::pipelines::PipelineStatusEnum;
+
+    #[test]
+    fn formats_line() {
+        let pipeline = PipelineSummary {
+            id: "gid://gitlab/Ci::Pipeline/1003".to_string(),
+            iid: "53".to_string(),
+            sha: Some("4a0e3d9f".to_string()),
+            git_ref: "main".to_string(),
+            author: "bob".to_string(),
+            created_at: chrono::Utc::now(),
+            duration: Some(252),
+            status: PipelineStatusEnum::SUCCESS,
+        };
+        let gitlab_ref = GitlabRef::from("group/project@main");
+
+        assert_eq!(
+            line(&gitlab_ref, Some(&pipeline), false, Format::Plain),
+            "✔ main 4m"
+        );
+        assert_eq!(
+            line(&gitlab_ref, Some(&pipeline), false, Format::Tmux),
+            "#[fg=#a6e3a1]✔ main 4m#[default]"
+        );
+        assert_eq!(
+            line(&gitlab_ref, Some(&pipeline), false, Format::Waybar),
+            r#"{"class":"success","text":"✔ main 4m","tooltip":"group/project@main pipeline #53 success"}"#
+        );
+        assert_eq!(
+            line(&gitlab_ref, None, false, Format::Plain),
+            "– group/project@main"
+        );
+        assert_eq!(
+            line(&gitlab_ref, Some(&pipeline), true, Format::Plain),
+            "✔ main 4m (stale)"
+        );
+        assert_eq!(
+            error_line(&gitlab_ref, "fetching pipelines failed", Format::Plain),
+            "⚠ group/project@main"
+        );
+    }
+}
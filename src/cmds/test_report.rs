@@ -0,0 +1,206 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{List, ListItem, ListState, Paragraph, Row, Table, Wrap};
+use ratatui::Frame;
+use std::io;
+use tokio::sync::mpsc::Receiver;
+
+use crate::events::*;
+use crate::fetchers::test_cases::{SuiteQueryArgs, TestCase};
+use crate::fetchers::{TestCounts, TestSummary};
+use crate::theme;
+use crate::theme::Theme;
+
+struct App {
+    summary: TestSummary,
+    receiver: Receiver<Result<Vec<TestCase>, String>>,
+    /// `None` until the failed test cases are fetched
+    failed: Option<Vec<TestCase>>,
+    /// fetching the failed test cases failed
+    error: Option<String>,
+    selected: ListState,
+}
+
+impl App {
+    fn new(
+        gapi: gitlab::AsyncGitlab,
+        project: String,
+        pipeline_iid: String,
+        summary: TestSummary,
+    ) -> Self {
+        let suites = summary
+            .suites
+            .iter()
+            .filter(|s| s.counts.failed + s.counts.errored > 0)
+            .map(|s| SuiteQueryArgs {
+                project: project.clone(),
+                pipeline_iid: pipeline_iid.clone(),
+                build_ids: s.build_ids.clone(),
+            })
+            .collect();
+        let receiver = crate::fetchers::test_cases::failed_tests(gapi, suites);
+
+        App {
+            summary,
+            receiver,
+            failed: None,
+            error: None,
+            selected: ListState::default(),
+        }
+    }
+
+    fn update(&mut self) {
+        match self.receiver.try_recv() {
+            Ok(Ok(failed)) => {
+                if !failed.is_empty() {
+                    self.selected.select_first();
+                }
+                self.failed = Some(failed);
+            }
+            Ok(Err(err)) => self.error = Some(err),
+            Err(_) => {}
+        }
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        let block = theme::Catpuccin
+            .block()
+            .title(Line::from("tests").left_aligned())
+            .title(Line::from(counts_line(&self.summary.total)).right_aligned());
+        frame.render_widget(&block, frame.area());
+
+        let suites_height = u16::try_from(self.summary.suites.len())
+            .unwrap_or(u16::MAX)
+            .min(5)
+            + 1;
+        let [suites_area, failed_area, detail_area] = Layout::vertical([
+            Constraint::Length(suites_height),
+            Constraint::Fill(1),
+            Constraint::Length(6),
+        ])
+        .areas(block.inner(frame.area()));
+
+        let suites = Table::new(
+            self.summary.suites.iter().map(|s| {
+                Row::new([
+                    s.name.clone(),
+                    s.counts.total.to_string(),
+                    s.counts.failed.to_string(),
+                    s.counts.errored.to_string(),
+                    s.counts.skipped.to_string(),
+                ])
+                .style(match s.counts.failed + s.counts.errored {
+                    0 => theme::Catpuccin::text(),
+                    _ => theme::Catpuccin::red(),
+                })
+            }),
+            [
+                Constraint::Fill(1),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(7),
+            ],
+        )
+        .header(
+            Row::new(["suite", "total", "failed", "error", "skipped"])
+                .style(theme::Catpuccin::text().add_modifier(Modifier::BOLD)),
+        );
+        frame.render_widget(suites, suites_area);
+
+        if let Some(err) = &self.error {
+            let error = Line::styled(err.clone(), theme::Catpuccin::red());
+            frame.render_widget(error, failed_area);
+            return;
+        }
+        let Some(failed) = &self.failed else {
+            frame.render_widget(
+                Line::from("fetching failed tests ...").style(theme::Catpuccin::text()),
+                failed_area,
+            );
+            return;
+        };
+
+        let items: Vec<ListItem> = failed
+            .iter()
+            .map(|c| {
+                let name = match &c.classname {
+                    Some(classname) => format!("{} › {} {}", c.suite, classname, c.name),
+                    None => format!("{} › {}", c.suite, c.name),
+                };
+                ListItem::new(name).style(theme::Catpuccin::red())
+            })
+            .collect();
+        let list = List::new(items).highlight_style(Modifier::REVERSED);
+        frame.render_stateful_widget(list, failed_area, &mut self.selected);
+
+        if let Some(case) = self.selected.selected().and_then(|i| failed.get(i)) {
+            let mut lines = vec![Line::from(format!(
+                "{:?} in {}",
+                case.status,
+                case.file.as_deref().unwrap_or("unknown file")
+            ))
+            .style(theme::Catpuccin::text().add_modifier(Modifier::BOLD))];
+            lines.extend(
+                case.output
+                    .as_deref()
+                    .unwrap_or("no output")
+                    .lines()
+                    .map(|l| Line::from(l.to_string()).style(theme::Catpuccin::text())),
+            );
+            frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), detail_area);
+        }
+    }
+}
+
+/// `120 total  3 failed  1 error  2 skipped`
+pub fn counts_line(counts: &TestCounts) -> String {
+    format!(
+        "{} total  {} failed  {} error  {} skipped",
+        counts.total, counts.failed, counts.errored, counts.skipped
+    )
+}
+
+/// Test suites of a pipeline and its failed test cases including their output.
+/// Returns once the user quits the view.
+pub async fn show(
+    gapi: gitlab::AsyncGitlab,
+    project: String,
+    pipeline_iid: String,
+    summary: TestSummary,
+    event_handler: &mut EventHandler,
+) {
+    let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
+    ratatui::crossterm::terminal::enable_raw_mode().expect("enable raw mode");
+    let viewport = ratatui::Viewport::Inline(20);
+    let mut terminal =
+        ratatui::Terminal::with_options(backend, ratatui::TerminalOptions { viewport })
+            .expect("terminal setup to work");
+
+    let mut app = App::new(gapi, project, pipeline_iid, summary);
+
+    loop {
+        app.update();
+        terminal
+            .draw(|frame| app.render(frame))
+            .expect("failed to draw frame");
+
+        match event_handler.next().await {
+            Event::Tick => {}
+            Event::Quit => break,
+            Event::Action(action) => match action {
+                Action::Quit => break,
+                Action::Up | Action::Prev => app.selected.select_previous(),
+                Action::Down | Action::Next => app.selected.select_next(),
+                _ => {}
+            },
+            Event::Key(_) => {}
+            Event::Mouse(mouse) => match mouse.kind {
+                MouseEventKind::ScrollUp => app.selected.select_previous(),
+                MouseEventKind::ScrollDown => app.selected.select_next(),
+                _ => {}
+            },
+            Event::Resize => terminal.autoresize().expect("failed to resize"),
+        }
+    }
+    terminal.clear().expect("failed to clear terminal");
+}
@@ -0,0 +1,81 @@
+use std::process;
+
+use clap::Args;
+
+use crate::cmds::Failure;
+use crate::fetchers::identity::{identity, token, Token};
+use crate::fetchers::rest::rejected;
+
+/// Any of these allows reading pipelines.
+const READ_SCOPES: &[&str] = &["read_api", "api"];
+
+/// Tokens expiring within this many days cause a warning.
+const EXPIRY_WARNING_DAYS: i64 = 14;
+
+#[derive(Debug, Args)]
+pub struct WhoamiArgs {}
+
+pub async fn run(gapi: gitlab::AsyncGitlab, host: &str, _args: &WhoamiArgs) {
+    let identity = match identity(&gapi).await {
+        Ok(i) => i,
+        Err(err) if rejected(&err) => {
+            eprintln!("the token was rejected by {} due to: {}", host, err);
+            process::exit(1);
+        }
+        Err(err) => {
+            eprintln!("asking {} about the token failed due to: {}", host, err);
+            process::exit(1);
+        }
+    };
+
+    println!("{} ({}) on {}", identity.user.username, identity.user.name, host);
+    let Some(token) = identity.token else {
+        println!("token: not a personal, project or group access token");
+        return;
+    };
+    let expiry = match (token.expires_at, days_left(&token)) {
+        (Some(at), Some(days)) => format!("expires {} (in {} days)", at, days),
+        _ => "never expires".to_string(),
+    };
+    println!("token: {}, scopes: {}, {}", token.name, token.scopes.join(", "), expiry);
+    if !can_read(&token) {
+        println!("the token lacks the read_api scope, gmon can not read pipelines with it");
+    }
+}
+
+/// Fails unless the token can read pipelines and warns if it expires soon.
+/// Only a token GitLab refuses fails, e.g. a network error is just a warning.
+/// Connecting asked GitLab for the user of the token already, so this only
+/// asks for the token and runs alongside the command.
+pub async fn check(gapi: gitlab::AsyncGitlab) -> Result<Option<String>, Failure> {
+    let token = match token(&gapi).await {
+        Ok(token) => token,
+        Err(err) if rejected(&err) => {
+            return Err(Failure::request(format!(
+                "the token was rejected by GitLab due to: {}\n\
+                 replace it in the config, e.g. with: gmon config init --force",
+                err
+            )));
+        }
+        Err(err) => return Ok(Some(format!("checking the token failed due to: {}", err))),
+    };
+
+    if !can_read(&token) {
+        return Err(Failure::request(format!(
+            "the token {} lacks the read_api scope, it has: {}",
+            token.name,
+            token.scopes.join(", ")
+        )));
+    }
+    let expiry = days_left(&token).filter(|d| *d <= EXPIRY_WARNING_DAYS);
+    Ok(expiry.map(|days| format!("the token {} expires in {} days", token.name, days.max(0))))
+}
+
+fn can_read(token: &Token) -> bool {
+    token.scopes.iter().any(|s| READ_SCOPES.contains(&s.as_str()))
+}
+
+fn days_left(token: &Token) -> Option<i64> {
+    let expires_at = token.expires_at?;
+    Some((expires_at - chrono::Utc::now().date_naive()).num_days())
+}
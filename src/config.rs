@@ -1,7 +1,151 @@
-use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fetchers::pipelines::PipelineStatusEnum;
+use crate::keymap::KeysConfig;
+
+/// Dashboards in this file of the current directory are preferred over the
+/// ones of the config; it is meant to be committed to a repository.
+pub const PROJECT_DASHBOARDS: &str = ".gmon.toml";
 
 #[derive(Deserialize)]
 pub struct Config {
     pub host: String,
     pub token: String,
+    #[serde(default)]
+    pub dashboards: HashMap<String, Dashboard>,
+    #[serde(default)]
+    pub keys: KeysConfig,
+    /// Secret token of the webhooks received by `gmon serve`
+    pub webhook_secret: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed reading config {} due to: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| format!("failed loading config {} due to: {}", path.display(), e.message()))
+    }
+}
+
+/// The config file: `explicit` (from `--config`), `$GMON_CONFIG`,
+/// `$XDG_CONFIG_HOME/gmon/config.toml` or `~/.config/gmon/config.toml`.
+pub fn config_path(explicit: Option<&Path>) -> Result<PathBuf, String> {
+    if let Some(path) = explicit {
+        return Ok(path.to_path_buf());
+    }
+    let non_empty = |name| env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(path) = non_empty("GMON_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = non_empty("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| non_empty("HOME").map(|h| PathBuf::from(h).join(".config")))
+        .ok_or("neither $XDG_CONFIG_HOME nor $HOME is set, pass the config with --config")?;
+    Ok(dir.join("gmon").join("config.toml"))
+}
+
+/// A file holding nothing but dashboards, see [`PROJECT_DASHBOARDS`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DashboardsFile {
+    #[serde(default)]
+    pub dashboards: HashMap<String, Dashboard>,
+}
+
+/// A set of refs whose pipelines are watched side by side.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Dashboard {
+    #[serde(default)]
+    pub layout: DashboardLayout,
+    pub refs: Vec<DashboardEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DashboardLayout {
+    /// one ref below the other
+    #[default]
+    Rows,
+    /// two refs next to each other
+    Grid,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DashboardEntry {
+    /// path/to/project or path/to/project@ref
+    #[serde(rename = "ref")]
+    pub gitlab_ref: String,
+    /// number of pipelines shown
+    #[serde(default = "default_count")]
+    pub count: i64,
+    /// only show pipelines with this status like `failed`
+    #[serde(default, deserialize_with = "status")]
+    pub status: Option<PipelineStatusEnum>,
+    /// show the chart of the pipeline durations
+    #[serde(default)]
+    pub durations: bool,
+}
+
+fn default_count() -> i64 {
+    30
+}
+
+/// Pipeline status in any case, GitLab itself only knows them in upper case.
+fn status<'de, D: Deserializer<'de>>(d: D) -> Result<Option<PipelineStatusEnum>, D::Error> {
+    let Some(status) = Option::<String>::deserialize(d)? else {
+        return Ok(None);
+    };
+    PipelineStatusEnum::deserialize(status.to_uppercase().into_deserializer()).map(Some)
+}
+
+impl DashboardsFile {
+    /// Reads `path`; a missing file has no dashboards.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(DashboardsFile {
+                    dashboards: HashMap::new(),
+                })
+            }
+            Err(e) => return Err(format!("failed reading {} due to: {}", path.display(), e)),
+        };
+        toml::from_str(&content)
+            .map_err(|e| format!("failed loading {} due to: {}", path.display(), e.message()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dashboards() {
+        let file: DashboardsFile = toml::from_str(
+            r#"
+            [dashboards.team]
+            layout = "grid"
+            refs = [
+                { ref = "group/project@main", count = 10, durations = true },
+                { ref = "group/other", status = "failed" },
+            ]
+            "#,
+        )
+        .expect("valid dashboards");
+
+        let team = &file.dashboards["team"];
+        assert_eq!(team.layout, DashboardLayout::Grid);
+        assert_eq!(team.refs[0].count, 10);
+        assert!(team.refs[0].durations);
+        assert_eq!(team.refs[1].count, 30);
+        assert_eq!(team.refs[1].status, Some(PipelineStatusEnum::FAILED));
+    }
 }
@@ -1,28 +1,53 @@
-use crossterm::event::{Event as CrosstermEvent, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{Event as CrosstermEvent, KeyCode, KeyModifiers, MouseEvent};
 use futures::{FutureExt, StreamExt};
+use ratatui::layout::{Position, Rect};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
+pub use crate::keymap::Action;
+pub use crossterm::event::{KeyEvent, MouseButton, MouseEventKind};
+
 #[derive(Clone, Copy, Debug)]
 pub enum Event {
     /// Quiet Action
     Quit,
     /// Terminal tick.
     Tick,
-    /// Key press.
+    /// Key press bound to an action.
+    Action(Action),
+    /// Any key press while text is typed, see [`EventHandler::text_input`].
     Key(KeyEvent),
+    /// Mouse click, scroll or movement.
+    Mouse(MouseEvent),
     /// Terminal resize.
-    Resize(u16, u16),
+    Resize,
+}
+
+/// The value of the area the mouse is in; the areas are the ones of the last
+/// rendered frame, their positions are absolute like the ones of the mouse.
+pub fn hit<T: Copy>(areas: &[(Rect, T)], mouse: &MouseEvent) -> Option<T> {
+    let position = Position::new(mouse.column, mouse.row);
+    areas
+        .iter()
+        .find(|(area, _)| area.contains(position))
+        .map(|(_, value)| *value)
 }
 
 #[derive(Debug)]
 pub struct EventHandler {
     receiver: mpsc::UnboundedReceiver<Event>,
+    /// keys are passed on as they are instead of as actions
+    text_input: Arc<AtomicBool>,
 }
 
 impl EventHandler {
     pub fn new(tick_rate: u64) -> Self {
         let tick_rate = std::time::Duration::from_millis(tick_rate);
         let (sender, receiver) = mpsc::unbounded_channel();
+        let keymap = crate::keymap::current();
+        let text_input = Arc::new(AtomicBool::new(false));
+        let raw_keys = text_input.clone();
 
         tokio::spawn(async move {
             let mut reader = crossterm::event::EventStream::new();
@@ -42,27 +67,24 @@ impl EventHandler {
                         match evt {
                             CrosstermEvent::Key(key) => {
                                 if key.kind == crossterm::event::KeyEventKind::Press {
-                                    match key.code {
-                                        // Exit application on `ESC` or `q`
-                                        KeyCode::Esc | KeyCode::Char('q') => {
-                                            sender.send(Event::Quit).unwrap();
-                                        }
-                                        // Exit application on `Ctrl-C`
-                                        KeyCode::Char('c') | KeyCode::Char('C') => {
-                                            if key.modifiers == KeyModifiers::CONTROL {
-                                                sender.send(Event::Quit).unwrap();
-                                            }
-                                        }
-                                        _ => {
-                                            sender.send(Event::Key(key)).unwrap();
-                                        }
+                                    let ctrl_c = matches!(key.code, KeyCode::Char('c') | KeyCode::Char('C'))
+                                        && key.modifiers == KeyModifiers::CONTROL;
+                                    // Exit application on `Ctrl-C` regardless of the keymap,
+                                    // unbound keys are ignored
+                                    if ctrl_c {
+                                        sender.send(Event::Quit).unwrap();
+                                    } else if raw_keys.load(Ordering::Relaxed) {
+                                        sender.send(Event::Key(key)).unwrap();
+                                    } else if let Some(action) = keymap.action(&key) {
+                                        sender.send(Event::Action(action)).unwrap();
                                     }
                                 }
                             },
-                            CrosstermEvent::Resize(x, y) => {
-                                sender.send(Event::Resize(x, y)).unwrap();
+                            CrosstermEvent::Resize(_, _) => {
+                                sender.send(Event::Resize).unwrap();
                             },
-                            CrosstermEvent::Mouse(_) => {
+                            CrosstermEvent::Mouse(mouse) => {
+                                sender.send(Event::Mouse(mouse)).unwrap();
                             },
                             CrosstermEvent::FocusLost => {
                             },
@@ -77,9 +99,16 @@ impl EventHandler {
         });
         Self {
             receiver,
+            text_input,
         }
     }
 
+    /// Passes all keys but `Ctrl-C` on as [`Event::Key`] while `on`, e.g. for
+    /// typing a search.
+    pub fn text_input(&self, on: bool) {
+        self.text_input.store(on, Ordering::Relaxed);
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
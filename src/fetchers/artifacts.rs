@@ -0,0 +1,147 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use gitlab::api::{Endpoint, QueryParams, RestClient};
+use http::Method;
+use tokio::io::AsyncWriteExt;
+
+use crate::fetchers::rest::{escape, ApiError};
+
+/// Where the artifact archive is taken from.
+pub enum ArtifactSource {
+    /// a specific job
+    Job(u64),
+    /// the job called `job` of the latest successful pipeline of `git_ref`
+    Ref { git_ref: String, job: String },
+}
+
+#[derive(Debug)]
+pub enum DownloadError {
+    /// the url could not be built
+    Api(ApiError),
+    Request(reqwest::Error),
+    /// GitLab answered, but not with the file
+    Status(http::StatusCode),
+    Write(io::Error),
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Api(err) => write!(f, "{}", err),
+            DownloadError::Request(err) => write!(f, "{}", err),
+            DownloadError::Status(status) => write!(f, "gitlab responded with {}", status),
+            DownloadError::Write(err) => write!(f, "writing failed: {}", err),
+        }
+    }
+}
+
+/// Download of either the whole artifact archive of a job or a single file of it.
+struct ArtifactDownload<'a> {
+    project: &'a str,
+    source: &'a ArtifactSource,
+    file: Option<&'a str>,
+}
+
+impl Endpoint for ArtifactDownload<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        let project = escape(self.project, false);
+        match (self.source, self.file) {
+            (ArtifactSource::Job(id), None) => {
+                format!("projects/{}/jobs/{}/artifacts", project, id)
+            }
+            (ArtifactSource::Job(id), Some(file)) => {
+                format!("projects/{}/jobs/{}/artifacts/{}", project, id, escape(file, true))
+            }
+            (ArtifactSource::Ref { git_ref, .. }, None) => {
+                format!("projects/{}/jobs/artifacts/{}/download", project, escape(git_ref, false))
+            }
+            (ArtifactSource::Ref { git_ref, .. }, Some(file)) => format!(
+                "projects/{}/jobs/artifacts/{}/raw/{}",
+                project,
+                escape(git_ref, false),
+                escape(file, true)
+            ),
+        }
+        .into()
+    }
+
+    fn parameters(&self) -> QueryParams<'_> {
+        let mut params = QueryParams::default();
+        if let ArtifactSource::Ref { job, .. } = self.source {
+            params.push("job", job.as_str());
+        }
+        params
+    }
+}
+
+/// Downloads the artifact archive (a zip file) or, if `file` is given, only
+/// that file out of the archive, into `path`.
+pub(crate) async fn download_artifacts(
+    gapi: &gitlab::AsyncGitlab,
+    project: &str,
+    source: &ArtifactSource,
+    file: Option<&str>,
+    path: &Path,
+) -> Result<(), DownloadError> {
+    let endpoint = ArtifactDownload {
+        project,
+        source,
+        file,
+    };
+    let mut url = gapi.rest_endpoint(&endpoint.endpoint()).map_err(DownloadError::Api)?;
+    endpoint.parameters().add_to_url(&mut url);
+    stream(url.as_str(), path).await
+}
+
+/// Downloads any artifact of a job, e.g. a report, by its `download_path`
+/// (like `/group/project/-/jobs/1/artifacts/download?file_type=junit`) into
+/// `path`; reports (junit, dotenv, ...) are not available through the api.
+pub(crate) async fn download_report(
+    gapi: &gitlab::AsyncGitlab,
+    download_path: &str,
+    path: &Path,
+) -> Result<(), DownloadError> {
+    let (endpoint, query) = match download_path.split_once('?') {
+        Some((endpoint, query)) => (endpoint, Some(query)),
+        None => (download_path, None),
+    };
+    let mut url = gapi.instance_endpoint(endpoint).map_err(DownloadError::Api)?;
+    // already encoded by GitLab
+    url.set_query(query);
+    stream(url.as_str(), path).await
+}
+
+/// Writes the response body to `path` chunk by chunk; a partly written file
+/// is removed again.
+async fn stream(url: &str, path: &Path) -> Result<(), DownloadError> {
+    let mut response = crate::fetchers::rest::get(url)
+        .send()
+        .await
+        .map_err(DownloadError::Request)?;
+    if !response.status().is_success() {
+        return Err(DownloadError::Status(response.status()));
+    }
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(dir).await.map_err(DownloadError::Write)?;
+    }
+    let mut out = tokio::fs::File::create(path).await.map_err(DownloadError::Write)?;
+    let written = async {
+        while let Some(chunk) = response.chunk().await.map_err(DownloadError::Request)? {
+            out.write_all(&chunk).await.map_err(DownloadError::Write)?;
+        }
+        out.flush().await.map_err(DownloadError::Write)
+    }
+    .await;
+    if written.is_err() {
+        _ = tokio::fs::remove_file(path).await;
+    }
+    written
+}
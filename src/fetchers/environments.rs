@@ -0,0 +1,161 @@
+use graphql_client::*;
+use tokio::sync::mpsc::{channel, Receiver};
+
+use crate::fetchers::poll::{Kind, Schedule};
+use crate::fetchers::projects::{find_project, FetchError};
+use crate::fetchers::source::Source;
+
+pub use crate::fetchers::environments::environments_query::{
+    CiJobStatus, DeploymentStatus, DeploymentTier,
+};
+
+type Time = chrono::DateTime<chrono::Utc>;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/environments.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct EnvironmentsQuery;
+
+pub struct EnvironmentsUpdate {
+    pub project: String,
+    pub environments: Vec<Environment>,
+    /// when the fetcher started the requests for this update
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub struct Environment {
+    pub name: String,
+    pub tier: Option<DeploymentTier>,
+    /// the most recent deployment, whatever its outcome
+    pub deployment: Option<Deployment>,
+    /// names of manual jobs waiting to deploy to this environment
+    pub pending_jobs: Vec<String>,
+}
+
+pub struct Deployment {
+    pub status: DeploymentStatus,
+    pub git_ref: String,
+    pub sha: String,
+    pub deployer: String,
+    /// when the deployment finished or, if it did not yet, was created
+    pub deployed_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct EnvironmentsQueryArgs {
+    project: String,
+}
+
+impl EnvironmentsQueryArgs {
+    pub fn new(project: String) -> Self {
+        return Self { project };
+    }
+}
+
+pub(crate) fn environments<S: Source>(
+    gapi: S,
+    params: EnvironmentsQueryArgs,
+    mut schedule: Schedule,
+) -> Receiver<Result<EnvironmentsUpdate, FetchError>> {
+    let (sender, receiver) = channel(1);
+    // deployments are jobs
+    schedule.watch(&params.project, Kind::Job);
+
+    let variables = <EnvironmentsQuery as GraphQLQuery>::Variables {
+        project: params.project.clone(),
+    };
+    let query = EnvironmentsQuery::build_query(variables);
+    tokio::spawn(async move {
+        loop {
+            let fetched_at = chrono::Utc::now();
+            let resp: <EnvironmentsQuery as GraphQLQuery>::ResponseData =
+                match gapi.query::<EnvironmentsQuery>(&query).await {
+                    Ok(resp) => resp,
+                    Err(err) => {
+                        let err = FetchError::request("the environments", err);
+                        // the view is gone, nobody is interested in updates anymore
+                        if sender.send(Err(err)).await.is_err() {
+                            break;
+                        }
+                        schedule.wait().await;
+                        continue;
+                    }
+                };
+            let Some(project) = resp.project else {
+                match find_project(&gapi, &params.project).await {
+                    // there is no point in asking again for a missing project
+                    Err(missing @ FetchError::NotFound(_)) => {
+                        _ = sender.send(Err(missing)).await;
+                        break;
+                    }
+                    Err(err) => {
+                        if sender.send(Err(err)).await.is_err() {
+                            break;
+                        }
+                    }
+                    // became accessible in the meantime
+                    Ok(()) => {}
+                }
+                schedule.wait().await;
+                continue;
+            };
+
+            let environments: Vec<_> = project
+                .environments
+                .into_iter()
+                .flat_map(|e| e.nodes)
+                .flatten()
+                .flatten()
+                .map(|e| Environment {
+                    name: e.name,
+                    tier: e.tier,
+                    deployment: e
+                        .latest
+                        .into_iter()
+                        .flat_map(|d| d.nodes)
+                        .flatten()
+                        .flatten()
+                        .map(|d| Deployment {
+                            status: d.status.unwrap_or(DeploymentStatus::CREATED),
+                            git_ref: d.ref_.unwrap_or("no name".to_string()),
+                            sha: d.sha.unwrap_or_default(),
+                            deployer: d
+                                .triggerer
+                                .map(|u| u.username)
+                                .unwrap_or("unknown".to_string()),
+                            deployed_at: d.finished_at.or(d.created_at),
+                        })
+                        .next(),
+                    pending_jobs: e
+                        .pending
+                        .into_iter()
+                        .flat_map(|d| d.nodes)
+                        .flatten()
+                        .flatten()
+                        .filter_map(|d| d.job)
+                        .filter(|j| j.status == Some(CiJobStatus::MANUAL))
+                        .map(|j| j.name.unwrap_or("no_name".to_string()))
+                        .collect(),
+                })
+                .collect();
+
+            let update = EnvironmentsUpdate {
+                project: params.project.clone(),
+                environments,
+                fetched_at,
+            };
+            // the view is gone, nobody is interested in updates anymore
+            if sender.send(Ok(update)).await.is_err() {
+                break;
+            }
+
+            schedule.wait().await;
+        }
+    });
+
+    return receiver;
+}
@@ -0,0 +1,204 @@
+use graphql_client::*;
+use std::collections::HashMap;
+use tokio::sync::mpsc::{channel, Receiver};
+
+use crate::fetchers::source::Source;
+
+pub use crate::fetchers::history::pipeline_history_query::{CiJobStatus, PipelineStatusEnum};
+
+type Time = chrono::DateTime<chrono::Utc>;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/history.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct PipelineHistoryQuery;
+
+/// Number of pipelines requested per page; every pipeline carries all of its
+/// jobs so the pages are kept small to stay below the query complexity limit.
+const PAGE_SIZE: i64 = 20;
+
+/// Upper bound of jobs fetched per pipeline.
+const JOB_LIMIT: usize = crate::fetchers::jobs::JOB_LIMIT;
+
+pub struct PipelineRun {
+    pub sha: String,
+    pub status: PipelineStatusEnum,
+    /// duration in seconds, only known for finished pipelines
+    pub duration: Option<i64>,
+    pub created_at: Time,
+    pub jobs: Vec<JobRun>,
+}
+
+pub struct JobRun {
+    pub name: String,
+    pub status: CiJobStatus,
+    /// duration in seconds, only known for finished jobs
+    pub duration: Option<i64>,
+    /// `None` for jobs that never ran, e.g. skipped ones
+    pub started_at: Option<Time>,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct HistoryQueryArgs {
+    project: String,
+    git_ref: Option<String>,
+    pipeline_count: usize,
+}
+
+impl HistoryQueryArgs {
+    pub fn new(project: String, count: usize) -> Self {
+        return Self {
+            project,
+            pipeline_count: count,
+            ..Self::default()
+        };
+    }
+    pub fn with_reference(mut self, reference: String) -> Self {
+        self.git_ref = Some(reference);
+        self
+    }
+}
+
+/// Fetches the last `count` pipelines (newest first) including all their jobs.
+pub(crate) async fn pipeline_history<S: Source>(
+    gapi: &S,
+    params: &HistoryQueryArgs,
+) -> Result<Vec<PipelineRun>, gitlab::GitlabError> {
+    let mut runs = Vec::with_capacity(params.pipeline_count);
+    let mut cursor: Option<String> = None;
+
+    while runs.len() < params.pipeline_count {
+        let remaining = (params.pipeline_count - runs.len()) as i64;
+        let variables = <PipelineHistoryQuery as GraphQLQuery>::Variables {
+            project: params.project.clone(),
+            git_ref: params.git_ref.clone(),
+            sha: None,
+            amount: Some(remaining.min(PAGE_SIZE)),
+            after: cursor.take(),
+            jobs_after: None,
+        };
+        let query = PipelineHistoryQuery::build_query(variables);
+        let resp = gapi.query::<PipelineHistoryQuery>(&query).await?;
+
+        let Some(pipelines) = resp.project.and_then(|p| p.pipelines) else {
+            break;
+        };
+
+        for mut p in pipelines.nodes.into_iter().flatten().flatten() {
+            let sha = p.sha.unwrap_or_default();
+            let jobs = all_jobs(gapi, params, &p.iid, &sha, p.jobs.take()).await?;
+            runs.push(PipelineRun {
+                sha,
+                status: p.status,
+                duration: p.duration,
+                created_at: p.created_at,
+                jobs: jobs
+                    .into_iter()
+                    .map(|j| JobRun {
+                        name: j.name.unwrap_or("no_name".to_string()),
+                        status: j.status.unwrap_or(CiJobStatus::CREATED),
+                        duration: j.duration,
+                        started_at: j.started_at,
+                    })
+                    .collect(),
+            });
+        }
+
+        if !pipelines.page_info.has_next_page {
+            break;
+        }
+        cursor = pipelines.page_info.end_cursor;
+    }
+
+    return Ok(runs);
+}
+
+type JobNode = pipeline_history_query::PipelineHistoryQueryProjectPipelinesNodesJobsNodes;
+
+/// The jobs of the first `page` plus the ones of all following pages of the pipeline.
+async fn all_jobs<S: Source>(
+    gapi: &S,
+    params: &HistoryQueryArgs,
+    iid: &str,
+    sha: &str,
+    page: Option<pipeline_history_query::PipelineHistoryQueryProjectPipelinesNodesJobs>,
+) -> Result<Vec<JobNode>, gitlab::GitlabError> {
+    let Some(mut page) = page else {
+        return Ok(Vec::new());
+    };
+
+    let mut jobs: Vec<JobNode> = Vec::new();
+    loop {
+        jobs.extend(page.nodes.into_iter().flatten().flatten());
+        if !page.page_info.has_next_page || jobs.len() >= JOB_LIMIT {
+            break;
+        }
+
+        // the sha narrows the pipelines down to (usually) exactly this one
+        let variables = <PipelineHistoryQuery as GraphQLQuery>::Variables {
+            project: params.project.clone(),
+            git_ref: None,
+            sha: Some(sha.to_string()),
+            amount: None,
+            after: None,
+            jobs_after: page.page_info.end_cursor.clone(),
+        };
+        let query = PipelineHistoryQuery::build_query(variables);
+        let resp = gapi.query::<PipelineHistoryQuery>(&query).await?;
+
+        let next = resp
+            .project
+            .and_then(|p| p.pipelines)
+            .into_iter()
+            .flat_map(|p| p.nodes)
+            .flatten()
+            .flatten()
+            .find(|p| p.iid == iid)
+            .and_then(|p| p.jobs);
+        match next {
+            Some(next) => page = next,
+            None => break,
+        }
+    }
+
+    Ok(jobs)
+}
+
+/// Median duration in seconds of every successful job (by name) of the last
+/// pipelines described by `params`. The medians are only computed once.
+pub(crate) fn job_medians<S: Source>(
+    gapi: S,
+    params: HistoryQueryArgs,
+) -> Receiver<HashMap<String, i64>> {
+    let (sender, receiver) = channel(1);
+
+    tokio::spawn(async move {
+        // the medians are only a nice to have; without them there is nothing to compare
+        let Ok(runs) = pipeline_history(&gapi, &params).await else {
+            return;
+        };
+
+        let mut durations: HashMap<String, Vec<i64>> = HashMap::new();
+        for job in runs.into_iter().flat_map(|r| r.jobs) {
+            if let (CiJobStatus::SUCCESS, Some(duration)) = (&job.status, job.duration) {
+                durations.entry(job.name).or_default().push(duration);
+            }
+        }
+
+        let medians = durations
+            .into_iter()
+            .map(|(name, mut d)| {
+                d.sort_unstable();
+                (name, d[d.len() / 2])
+            })
+            .collect();
+
+        _ = sender.send(medians).await;
+    });
+
+    return receiver;
+}
@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+
+use gitlab::api::{AsyncQuery, Endpoint};
+use http::Method;
+use serde::Deserialize;
+
+use crate::fetchers::rest::ApiError;
+
+/// The user the token belongs to.
+struct CurrentUser;
+
+impl Endpoint for CurrentUser {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        "user".into()
+    }
+}
+
+/// The token used for the request itself.
+struct CurrentToken;
+
+impl Endpoint for CurrentToken {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        "personal_access_tokens/self".into()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub username: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Token {
+    pub name: String,
+    /// like `read_api` or `api`
+    pub scopes: Vec<String>,
+    /// tokens without expiry exist on older GitLab versions only
+    pub expires_at: Option<chrono::NaiveDate>,
+}
+
+pub struct Identity {
+    pub user: User,
+    /// `None` for tokens which can not describe themselves, e.g. OAuth tokens
+    pub token: Option<Token>,
+}
+
+pub(crate) async fn identity(gapi: &gitlab::AsyncGitlab) -> Result<Identity, ApiError> {
+    let user = CurrentUser.query_async(gapi).await?;
+    let token = token(gapi).await.ok();
+    Ok(Identity { user, token })
+}
+
+/// The token used for the request, without asking who it belongs to.
+pub(crate) async fn token(gapi: &gitlab::AsyncGitlab) -> Result<Token, ApiError> {
+    CurrentToken.query_async(gapi).await
+}
@@ -0,0 +1,57 @@
+use std::borrow::Cow;
+
+use gitlab::api::{AsyncQuery, Endpoint};
+use http::Method;
+use serde::Deserialize;
+
+use crate::fetchers::rest::{escape, ApiError};
+
+#[derive(Debug, Clone, Copy)]
+pub enum JobCommand {
+    /// creates a new job running the same script
+    Retry,
+    Cancel,
+}
+
+struct ControlJob<'a> {
+    project: &'a str,
+    job: u64,
+    command: JobCommand,
+}
+
+impl Endpoint for ControlJob<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        let command = match self.command {
+            JobCommand::Retry => "retry",
+            JobCommand::Cancel => "cancel",
+        };
+        format!("projects/{}/jobs/{}/{}", escape(self.project, false), self.job, command).into()
+    }
+}
+
+/// The job as returned by GitLab after the command.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ControlledJob {
+    pub id: u64,
+    pub status: String,
+}
+
+/// Retries or cancels the job with the (REST) id `job`.
+pub(crate) async fn control_job(
+    gapi: &gitlab::AsyncGitlab,
+    project: &str,
+    job: u64,
+    command: JobCommand,
+) -> Result<ControlledJob, ApiError> {
+    ControlJob {
+        project,
+        job,
+        command,
+    }
+    .query_async(gapi)
+    .await
+}
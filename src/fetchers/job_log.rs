@@ -0,0 +1,185 @@
+use std::borrow::Cow;
+
+use gitlab::api::{AsyncQuery, Endpoint, RestClient};
+use http::{header, Method, StatusCode};
+use serde::Deserialize;
+use tokio::sync::mpsc::{channel, Receiver};
+
+use crate::fetchers::poll::{Kind, Schedule};
+use crate::fetchers::rest::escape;
+
+/// A job, or its log (trace) as plain text.
+struct JobEndpoint<'a> {
+    project: &'a str,
+    job: u64,
+    log: bool,
+}
+
+impl Endpoint for JobEndpoint<'_> {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        let job = format!("projects/{}/jobs/{}", escape(self.project, false), self.job);
+        match self.log {
+            true => format!("{}/trace", job).into(),
+            false => job.into(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JobState {
+    status: String,
+}
+
+/// The log does not change anymore in these states.
+const FINAL: [&str; 4] = ["success", "failed", "canceled", "skipped"];
+
+pub struct JobLogUpdate {
+    pub lines: Vec<String>,
+    /// the final state of the job once it ended, the log is complete then and
+    /// not fetched again
+    pub finished: Option<String>,
+    /// when the fetcher started the requests for this update
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Fetches the log of the job with the (REST) id `job` on every poll, as it
+/// grows while the job runs, asking only for the bytes added since. Failed
+/// requests are sent as error and retried, polling ends with the job.
+pub(crate) fn job_log(
+    gapi: gitlab::AsyncGitlab,
+    project: String,
+    job: u64,
+    mut schedule: Schedule,
+) -> Receiver<Result<JobLogUpdate, String>> {
+    let (sender, receiver) = channel(1);
+    schedule.watch(&project, Kind::Job);
+
+    tokio::spawn(async move {
+        let mut log = Vec::new();
+        loop {
+            let fetched_at = chrono::Utc::now();
+            // the state first, a final state means the log fetched after it is complete
+            let state = JobEndpoint {
+                project: &project,
+                job,
+                log: false,
+            };
+            let state: Result<JobState, _> = state.query_async(&gapi).await;
+            let update = match state {
+                Ok(state) => match append(&gapi, &project, job, &mut log).await {
+                    Ok(()) => Ok(JobLogUpdate {
+                        lines: log_lines(&String::from_utf8_lossy(&log)),
+                        finished: Some(state.status)
+                            .filter(|status| FINAL.contains(&status.as_str())),
+                        fetched_at,
+                    }),
+                    Err(err) => Err(format!("fetching the log failed: {}", err)),
+                },
+                Err(err) => Err(format!("fetching the job failed: {}", err)),
+            };
+            let finished = matches!(&update, Ok(u) if u.finished.is_some());
+            // the view is gone, nobody is interested in updates anymore
+            if sender.send(update).await.is_err() || finished {
+                break;
+            }
+
+            schedule.wait().await;
+        }
+    });
+
+    return receiver;
+}
+
+/// Appends what was added to the log since it was `log`, the whole log if
+/// GitLab ignores the range.
+async fn append(
+    gapi: &gitlab::AsyncGitlab,
+    project: &str,
+    job: u64,
+    log: &mut Vec<u8>,
+) -> Result<(), String> {
+    let endpoint = JobEndpoint {
+        project,
+        job,
+        log: true,
+    };
+    let url = gapi.rest_endpoint(&endpoint.endpoint()).map_err(|err| err.to_string())?;
+    let response = crate::fetchers::rest::get(url.as_str())
+        .header(header::RANGE, format!("bytes={}-", log.len()))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    let status = response.status();
+    match status {
+        // nothing was added
+        StatusCode::RANGE_NOT_SATISFIABLE => return Ok(()),
+        StatusCode::OK | StatusCode::PARTIAL_CONTENT => {}
+        _ => return Err(format!("gitlab responded with {}", status)),
+    }
+    let body = response.bytes().await.map_err(|err| err.to_string())?;
+    if status == StatusCode::OK {
+        log.clear();
+    }
+    log.extend_from_slice(&body);
+    Ok(())
+}
+
+/// The lines of a log as the terminal of the runner showed them: without
+/// colors, the section markers of GitLab and the progress overwritten by `\r`.
+pub(crate) fn log_lines(log: &str) -> Vec<String> {
+    log.lines()
+        .map(|line| {
+            let line = strip_escapes(line);
+            let line = line.trim_end_matches('\r');
+            let shown = line.rsplit('\r').next().unwrap_or(line);
+            match shown.starts_with("section_") {
+                // e.g. `section_start:1700000000:step_script` without a header
+                true => String::new(),
+                false => shown.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Removes the ANSI escape sequences like `\x1b[32;1m` or `\x1b[0K`.
+fn strip_escapes(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            // parameters up to the final byte of the sequence
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleans_colors_sections_and_progress() {
+        let log = "\x1b[0KRunning with gitlab-runner 17.0\n\
+                   section_start:1700000000:step_script\r\x1b[0K\x1b[32;1m$ make\x1b[0;m\n\
+                   10%\r50%\r100%\n\
+                   section_end:1700000010:step_script\r\x1b[0K\n";
+
+        assert_eq!(
+            log_lines(log),
+            vec!["Running with gitlab-runner 17.0", "$ make", "100%", ""]
+        );
+    }
+}
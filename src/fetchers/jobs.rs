@@ -1,7 +1,22 @@
 use graphql_client::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tokio::sync::mpsc::{channel, Receiver};
 
-pub use crate::fetchers::jobs::jobs_query::{CiJobStatus, PipelineStatusEnum};
+use crate::fetchers::poll::{Kind, Schedule};
+use crate::fetchers::projects::{find_project, FetchError};
+use crate::fetchers::source::Source;
+
+pub use crate::fetchers::jobs::jobs_query::{CiJobStatus, JobArtifactFileType, PipelineStatusEnum};
+
+type Time = chrono::DateTime<chrono::Utc>;
+type JobID = String;
+
+/// GitLab does not return more than 100 nodes per page.
+const PAGE_SIZE: i64 = 100;
+
+/// Upper bound of jobs fetched per pipeline unless configured otherwise.
+pub const JOB_LIMIT: usize = 1000;
 
 #[derive(GraphQLQuery)]
 #[graphql(
@@ -12,36 +27,125 @@ pub use crate::fetchers::jobs::jobs_query::{CiJobStatus, PipelineStatusEnum};
 )]
 struct JobsQuery;
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/pipeline_iids.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct PipelineIidsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/test_summary.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct TestSummaryQuery;
+
+#[derive(Serialize, Deserialize)]
 pub struct Project {
     pub id: String,
     pub full_path: String,
     pub pipelines: Vec<Pipeline>,
+    /// when the fetcher started the requests for this update
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Pipeline {
     pub id: String,
+    pub iid: String,
     pub name: String,
     pub git_ref: String,
     pub status: PipelineStatusEnum,
+    /// only present if the pipeline finished and reported test results
+    pub tests: Option<TestSummary>,
     pub stages: Vec<Stage>,
 }
 
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TestCounts {
+    pub total: i64,
+    pub failed: i64,
+    pub skipped: i64,
+    pub errored: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TestSummary {
+    pub total: TestCounts,
+    pub suites: Vec<TestSuiteSummary>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TestSuiteSummary {
+    pub name: String,
+    pub counts: TestCounts,
+    /// the jobs which reported the suite; required to query its test cases
+    pub build_ids: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Stage {
     pub name: String,
     pub jobs: Vec<Job>,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Job {
+    pub id: String,
     pub name: String,
     pub status: CiJobStatus,
+    /// duration in seconds, only known for finished jobs
+    pub duration: Option<i64>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub artifacts: Vec<Artifact>,
+    /// pipeline triggered by this (trigger) job
+    pub downstream: Option<Box<Downstream>>,
+}
+
+/// A child or multi-project pipeline triggered by a job; its jobs are only
+/// fetched on demand, see [`JobQueryParams::with_pipeline`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Downstream {
+    pub project: String,
+    pub iid: String,
+    pub status: PipelineStatusEnum,
+}
+
+impl Job {
+    /// Numeric id of the job as used by the REST api.
+    pub fn rest_id(&self) -> Option<u64> {
+        self.id.rsplit('/').next().and_then(|id| id.parse().ok())
+    }
+
+    /// Seconds the job took or, if it is still running, has taken so far.
+    pub fn elapsed(&self) -> Option<i64> {
+        self.duration.or_else(|| {
+            self.started_at
+                .map(|s| chrono::Utc::now().signed_duration_since(s).num_seconds())
+        })
+    }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Artifact {
+    pub name: String,
+    pub file_type: JobArtifactFileType,
+    /// web path of the file, see [`crate::fetchers::artifacts::download_report`]
+    pub download_path: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, Serialize)]
 pub struct JobQueryParams {
     project: String,
     git_ref: Option<String>,
+    iid: Option<String>,
     pipeline_count: Option<i64>,
-    pipeline_status: Option<PipelineStatusEnum>,
+    job_limit: Option<usize>,
 }
 
 impl JobQueryParams {
@@ -59,83 +163,397 @@ impl JobQueryParams {
         self.pipeline_count = Some(count);
         self
     }
-    pub fn with_status(mut self, status: PipelineStatusEnum) -> Self {
-        self.pipeline_status = Some(status);
+    /// Restrict the query to exactly one pipeline.
+    pub fn with_pipeline(mut self, iid: String) -> Self {
+        self.iid = Some(iid);
         self
     }
+    /// Stop following the pages of jobs of a pipeline after `limit` jobs.
+    pub fn with_job_limit(mut self, limit: usize) -> Self {
+        self.job_limit = Some(limit);
+        self
+    }
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+    pub fn job_limit(&self) -> usize {
+        self.job_limit.unwrap_or(JOB_LIMIT)
+    }
 }
 
-pub(crate) fn jobs_pipelines(
-    gapi: gitlab::AsyncGitlab,
+pub(crate) fn jobs_pipelines<S: Source>(
+    gapi: S,
     params: JobQueryParams,
-) -> Receiver<Project> {
+    mut schedule: Schedule,
+) -> Receiver<Result<Project, FetchError>> {
     let (sender, receiver) = channel(1);
-
-    let variables = <JobsQuery as GraphQLQuery>::Variables {
-        project: params.project.clone(),
-        git_ref: params.git_ref.clone(),
-        amount: params.pipeline_count.clone(),
-    };
-    let query = JobsQuery::build_query(variables);
+    schedule.watch(&params.project, Kind::Pipeline);
+    schedule.watch(&params.project, Kind::Job);
 
     tokio::spawn(async move {
+        // the test reports of finished pipelines by their iid along with the status they
+        // were fetched at; retrying a job finishes the pipeline again, maybe differently
+        let mut summaries: HashMap<String, (PipelineStatusEnum, Option<TestSummary>)> =
+            HashMap::new();
         loop {
-            let resp: <JobsQuery as GraphQLQuery>::ResponseData =
-                gapi.graphql::<JobsQuery>(&query).await.expect("some data");
+            let mut update = match fetch_project(&gapi, &params).await {
+                Ok(Some(update)) => update,
+                Ok(None) => match find_project(&gapi, &params.project).await {
+                    // there is no point in asking again for a missing project
+                    Err(missing @ FetchError::NotFound(_)) => {
+                        _ = sender.send(Err(missing)).await;
+                        break;
+                    }
+                    Err(err) => {
+                        if sender.send(Err(err)).await.is_err() {
+                            break;
+                        }
+                        schedule.wait().await;
+                        continue;
+                    }
+                    // became accessible in the meantime
+                    Ok(()) => {
+                        schedule.wait().await;
+                        continue;
+                    }
+                },
+                Err(err) => {
+                    // the view is gone, nobody is interested in updates anymore
+                    if sender.send(Err(err)).await.is_err() {
+                        break;
+                    }
+                    schedule.wait().await;
+                    continue;
+                }
+            };
+
+            for pipeline in update.pipelines.iter_mut().filter(|p| finished(&p.status)) {
+                let known = summaries.get(&pipeline.iid);
+                if known.is_none_or(|(status, _)| *status != pipeline.status) {
+                    // a failed request is made again on the next poll
+                    let summary = fetch_test_summary(&gapi, &params.project, &pipeline.iid).await;
+                    match summary {
+                        Ok(summary) => {
+                            let status = pipeline.status.clone();
+                            summaries.insert(pipeline.iid.clone(), (status, summary));
+                        }
+                        // the report of the previous run is outdated
+                        Err(_) => _ = summaries.remove(&pipeline.iid),
+                    }
+                }
+                pipeline.tests = summaries.get(&pipeline.iid).and_then(|(_, s)| s.clone());
+            }
 
-            if resp.project.is_none() {
-                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                continue;
+            // the view is gone, nobody is interested in updates anymore
+            if sender.send(Ok(update)).await.is_err() {
+                break;
             }
 
-            let r_project = resp.project.unwrap();
-            let pipelines: Vec<_> = r_project
-                .pipelines
-                .into_iter()
-                .filter_map(|p| p.nodes)
-                .flat_map(|p| p)
-                .filter_map(|p| p)
-                .map(|p| Pipeline {
-                    id: p.id,
-                    name: p.name.unwrap_or("no name".to_string()),
-                    git_ref: p.ref_.unwrap_or("no name".to_string()),
-                    status: p.status,
-                    stages: p
-                        .stages
-                        .into_iter()
-                        .filter_map(|s| s.nodes)
-                        .flat_map(|s| s)
-                        .filter_map(|s| s)
-                        .map(|s| Stage {
-                            name: s.name.unwrap_or("no name".to_string()),
-                            jobs: s
-                                .jobs
-                                .into_iter()
-                                .filter_map(|s| s.nodes)
-                                .flat_map(|s| s)
-                                .filter_map(|s| s)
-                                .map(|j| Job {
-                                    name: j.name.unwrap_or("no_name".to_string()),
-                                    status: j.status.unwrap_or(CiJobStatus::CREATED),
-                                })
-                                .collect(),
-                        })
-                        .collect(),
-                })
-                .collect();
-
-            sender
-                .send(Project {
-                    id: r_project.id,
-                    full_path: r_project.full_path,
-                    pipelines,
-                })
-                .await
-                .expect("sending");
-
-            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            schedule.wait().await;
         }
     });
 
     return receiver;
 }
+
+/// Fetches the pipelines described by `params`; `None` if the project is missing.
+async fn fetch_project<S: Source>(
+    gapi: &S,
+    params: &JobQueryParams,
+) -> Result<Option<Project>, FetchError> {
+    let fetched_at = chrono::Utc::now();
+    let (mut project, iids) = match &params.iid {
+        Some(iid) => (None, vec![iid.clone()]),
+        None => {
+            let Some((project, iids)) = pipeline_iids(gapi, params).await? else {
+                return Ok(None);
+            };
+            (Some(project), iids)
+        }
+    };
+
+    let mut pipelines: Vec<Pipeline> = Vec::new();
+    for iid in &iids {
+        let Some((r_project, pipeline)) = fetch_pipeline(gapi, params, iid).await? else {
+            return Ok(None);
+        };
+        project.get_or_insert(r_project);
+        pipelines.extend(pipeline);
+    }
+
+    let Some((id, full_path)) = project else {
+        return Ok(None);
+    };
+    Ok(Some(Project {
+        id,
+        full_path,
+        pipelines,
+        fetched_at,
+    }))
+}
+
+/// The id and full path of the project and the iids of its latest
+/// `pipeline_count` pipelines of the ref; `None` if the project is missing.
+async fn pipeline_iids<S: Source>(
+    gapi: &S,
+    params: &JobQueryParams,
+) -> Result<Option<((String, String), Vec<String>)>, FetchError> {
+    let mut iids = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let amount = match params.pipeline_count {
+            Some(count) => (count - iids.len() as i64).min(PAGE_SIZE),
+            None => PAGE_SIZE,
+        };
+        let variables = <PipelineIidsQuery as GraphQLQuery>::Variables {
+            project: params.project.clone(),
+            git_ref: params.git_ref.clone(),
+            amount: Some(amount),
+            after: after.take(),
+        };
+        let query = PipelineIidsQuery::build_query(variables);
+        let resp: <PipelineIidsQuery as GraphQLQuery>::ResponseData = gapi
+            .query::<PipelineIidsQuery>(&query)
+            .await
+            .map_err(|err| FetchError::request("pipelines", err))?;
+
+        let Some(r_project) = resp.project else {
+            return Ok(None);
+        };
+        let project = (r_project.id, r_project.full_path);
+        let Some(page) = r_project.pipelines else {
+            return Ok(Some((project, iids)));
+        };
+        iids.extend(page.nodes.into_iter().flatten().flatten().map(|p| p.iid));
+
+        // only one page without a count, there is no point in fetching all pipelines
+        let complete = params
+            .pipeline_count
+            .is_none_or(|count| iids.len() as i64 >= count);
+        if complete || !page.page_info.has_next_page {
+            return Ok(Some((project, iids)));
+        }
+        after = page.page_info.end_cursor;
+    }
+}
+
+type JobNode = jobs_query::JobsQueryProjectPipelineJobsNodes;
+
+/// The id and full path of the project and its pipeline `iid` with all of its
+/// jobs, following their pages up to the job limit of `params`; `None` if the
+/// project is missing.
+async fn fetch_pipeline<S: Source>(
+    gapi: &S,
+    params: &JobQueryParams,
+    iid: &str,
+) -> Result<Option<((String, String), Option<Pipeline>)>, FetchError> {
+    let limit = params.job_limit();
+    let mut jobs: Vec<JobNode> = Vec::new();
+    let mut jobs_after: Option<String> = None;
+
+    loop {
+        let variables = <JobsQuery as GraphQLQuery>::Variables {
+            project: params.project.clone(),
+            iid: iid.to_string(),
+            jobs_after: jobs_after.take(),
+        };
+        let query = JobsQuery::build_query(variables);
+        let resp: <JobsQuery as GraphQLQuery>::ResponseData = gapi
+            .query::<JobsQuery>(&query)
+            .await
+            .map_err(|err| FetchError::request("the jobs", err))?;
+
+        let Some(r_project) = resp.project else {
+            return Ok(None);
+        };
+        let project = (r_project.id, r_project.full_path);
+        let Some(mut p) = r_project.pipeline else {
+            return Ok(Some((project, None)));
+        };
+
+        let page = p.jobs.take();
+        let next = page.as_ref().and_then(|page| {
+            let info = &page.page_info;
+            info.has_next_page.then(|| info.end_cursor.clone()).flatten()
+        });
+        jobs.extend(page.into_iter().flat_map(|page| page.nodes).flatten().flatten());
+        match next {
+            Some(cursor) if jobs.len() < limit => jobs_after = Some(cursor),
+            _ => {
+                jobs.truncate(limit);
+                return Ok(Some((project, Some(pipeline(p, jobs)))));
+            }
+        }
+    }
+}
+
+/// Groups the jobs by their stage, the stages are kept in pipeline order.
+fn pipeline(p: jobs_query::JobsQueryProjectPipeline, jobs: Vec<JobNode>) -> Pipeline {
+    let mut stages: Vec<Stage> = p
+        .stages
+        .into_iter()
+        .filter_map(|s| s.nodes)
+        .flatten()
+        .flatten()
+        .map(|s| Stage {
+            name: s.name.unwrap_or("no name".to_string()),
+            jobs: Vec::new(),
+        })
+        .collect();
+
+    for j in jobs {
+        let stage_name = j
+            .stage
+            .as_ref()
+            .and_then(|s| s.name.clone())
+            .unwrap_or("no name".to_string());
+        let job = job(j);
+        match stages.iter_mut().find(|s| s.name == stage_name) {
+            Some(stage) => stage.jobs.push(job),
+            None => stages.push(Stage {
+                name: stage_name,
+                jobs: vec![job],
+            }),
+        }
+    }
+    stages.retain(|s| !s.jobs.is_empty());
+
+    Pipeline {
+        id: p.id,
+        iid: p.iid,
+        name: p.name.unwrap_or("no name".to_string()),
+        git_ref: p.ref_.unwrap_or("no name".to_string()),
+        status: p.status,
+        tests: None,
+        stages,
+    }
+}
+
+fn job(j: JobNode) -> Job {
+    Job {
+        id: j.id.unwrap_or_default(),
+        name: j.name.unwrap_or("no_name".to_string()),
+        status: j.status.unwrap_or(CiJobStatus::CREATED),
+        duration: j.duration,
+        started_at: j.started_at,
+        artifacts: j
+            .artifacts
+            .into_iter()
+            .filter_map(|a| a.nodes)
+            .flatten()
+            .flatten()
+            .map(|a| Artifact {
+                name: a.name.unwrap_or("no_name".to_string()),
+                file_type: a.file_type.unwrap_or(JobArtifactFileType::ARCHIVE),
+                download_path: a.download_path,
+            })
+            .collect(),
+        downstream: j.downstream_pipeline.and_then(|d| {
+            Some(Box::new(Downstream {
+                project: d.project?.full_path,
+                iid: d.iid,
+                status: d.status,
+            }))
+        }),
+    }
+}
+
+fn finished(status: &PipelineStatusEnum) -> bool {
+    matches!(
+        status,
+        PipelineStatusEnum::SUCCESS
+            | PipelineStatusEnum::FAILED
+            | PipelineStatusEnum::CANCELED
+            | PipelineStatusEnum::SKIPPED
+    )
+}
+
+/// The test report of the pipeline `iid`; `None` if it reported no tests.
+async fn fetch_test_summary<S: Source>(
+    gapi: &S,
+    project: &str,
+    iid: &str,
+) -> Result<Option<TestSummary>, FetchError> {
+    let variables = <TestSummaryQuery as GraphQLQuery>::Variables {
+        project: project.to_string(),
+        iid: iid.to_string(),
+    };
+    let query = TestSummaryQuery::build_query(variables);
+    let resp: <TestSummaryQuery as GraphQLQuery>::ResponseData = gapi
+        .query::<TestSummaryQuery>(&query)
+        .await
+        .map_err(|err| FetchError::request("the test report", err))?;
+    let summary = resp.project.and_then(|p| p.pipeline);
+    Ok(summary.and_then(|p| test_summary(p.test_report_summary)))
+}
+
+fn test_summary(
+    summary: test_summary_query::TestSummaryQueryProjectPipelineTestReportSummary,
+) -> Option<TestSummary> {
+    let total = TestCounts {
+        total: summary.total.count.unwrap_or(0),
+        failed: summary.total.failed.unwrap_or(0),
+        skipped: summary.total.skipped.unwrap_or(0),
+        errored: summary.total.error.unwrap_or(0),
+    };
+    if total.total == 0 {
+        return None;
+    }
+
+    Some(TestSummary {
+        total,
+        suites: summary
+            .test_suites
+            .nodes
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|s| TestSuiteSummary {
+                name: s.name.unwrap_or("no name".to_string()),
+                counts: TestCounts {
+                    total: s.total_count.unwrap_or(0),
+                    failed: s.failed_count.unwrap_or(0),
+                    skipped: s.skipped_count.unwrap_or(0),
+                    errored: s.error_count.unwrap_or(0),
+                },
+                build_ids: s.build_ids.unwrap_or_default(),
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn job_names(project: &Project) -> Vec<&str> {
+        let stages = project.pipelines.iter().flat_map(|p| &p.stages);
+        stages.flat_map(|s| &s.jobs).map(|j| j.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn follows_the_pages_of_jobs() {
+        let params = JobQueryParams::new("group/paged".to_string()).with_pipeline("9".to_string());
+        let project = fetch_project(&testing::fixtures(), &params)
+            .await
+            .expect("fetched")
+            .expect("existing project");
+
+        assert_eq!(job_names(&project), ["compile", "lint", "unit"]);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_job_limit() {
+        let params = JobQueryParams::new("group/paged".to_string())
+            .with_pipeline("9".to_string())
+            .with_job_limit(1);
+        let project = fetch_project(&testing::fixtures(), &params)
+            .await
+            .expect("fetched")
+            .expect("existing project");
+
+        assert_eq!(job_names(&project), ["compile"]);
+    }
+}
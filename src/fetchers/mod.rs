@@ -1,5 +1,18 @@
+pub mod artifacts;
+pub mod environments;
+pub mod history;
+pub mod identity;
+pub mod job_control;
+pub mod job_log;
 pub mod jobs;
 pub mod pipelines;
+pub mod poll;
+pub mod projects;
+pub mod rest;
+pub mod source;
+pub mod store;
+pub mod test_cases;
+pub mod trigger;
 
 pub use jobs::*;
 pub use pipelines::*;
@@ -1,8 +1,17 @@
 use graphql_client::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
 use tokio::sync::mpsc::{channel, Receiver};
 
+use crate::fetchers::poll::{Kind, Schedule};
+use crate::fetchers::projects::{find_project, FetchError, NotFound};
+use crate::fetchers::source::Source;
+
 pub use crate::fetchers::pipelines::branch_pipelines_query::PipelineStatusEnum;
 
+type Time = chrono::DateTime<chrono::Utc>;
+
 #[derive(GraphQLQuery)]
 #[graphql(
     query_path = "graphql/pipelines.graphql",
@@ -12,13 +21,30 @@ pub use crate::fetchers::pipelines::branch_pipelines_query::PipelineStatusEnum;
 )]
 struct BranchPipelinesQuery;
 
+#[derive(Serialize, Deserialize)]
 pub struct BranchPipelineUpdate {
     pub project: String,
     pub branch: Option<String>,
-    pub states: Vec<PipelineStatusEnum>,
+    pub pipelines: Vec<PipelineSummary>,
+    /// when the fetcher started the requests for this update
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSummary {
+    pub id: String,
+    pub iid: String,
+    /// `None` e.g. for pipelines of a deleted commit
+    pub sha: Option<String>,
+    pub git_ref: String,
+    pub author: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// duration in seconds, only known for finished pipelines
+    pub duration: Option<i64>,
+    pub status: PipelineStatusEnum,
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, Serialize)]
 pub struct PipelinesQueryArgs {
     project: String,
     git_ref: Option<String>,
@@ -47,45 +73,413 @@ impl PipelinesQueryArgs {
     }
 }
 
-pub(crate) fn branch_pipelines(
-    gapi: gitlab::AsyncGitlab,
+/// GitLab does not return more than 100 nodes per page.
+const PAGE_SIZE: i64 = 100;
+
+pub(crate) fn branch_pipelines<S: Source>(
+    gapi: S,
     params: PipelinesQueryArgs,
-) -> Receiver<BranchPipelineUpdate> {
+    mut schedule: Schedule,
+) -> Receiver<Result<BranchPipelineUpdate, FetchError>> {
     let (sender, receiver) = channel(1);
+    // job events do not change the summaries
+    schedule.watch(&params.project, Kind::Pipeline);
 
-    let variables = <BranchPipelinesQuery as GraphQLQuery>::Variables {
-        project: params.project.clone(),
-        branch: params.git_ref.clone(),
-        amount: params.pipeline_count,
-    };
-    let query = BranchPipelinesQuery::build_query(variables);
     tokio::spawn(async move {
         loop {
-            let resp: <BranchPipelinesQuery as GraphQLQuery>::ResponseData = gapi
-                .graphql::<BranchPipelinesQuery>(&query)
-                .await
-                .expect("some data");
-
-            let states: Vec<_> = resp.project.into_iter()
-                .flat_map(|p| p.pipelines)
-                .flat_map(|p| p.nodes)
-                .flatten()
-                .filter_map(|p| p)
-                .map(|p| p.status)
+            let fetched_at = chrono::Utc::now();
+            let update =
+                fetch_pipelines(&gapi, &params)
+                    .await
+                    .map(|pipelines| BranchPipelineUpdate {
+                        project: params.project.clone(),
+                        branch: params.git_ref.clone(),
+                        pipelines,
+                        fetched_at,
+                    });
+            // there is no point in asking again for a missing project
+            let missing = matches!(update, Err(FetchError::NotFound(_)));
+            // the view is gone, nobody is interested in updates anymore
+            if sender.send(update).await.is_err() || missing {
+                break;
+            }
+
+            schedule.wait().await;
+        }
+    });
+
+    return receiver;
+}
+
+/// Follows the pages of pipelines until `pipeline_count` pipelines are fetched.
+async fn fetch_pipelines<S: Source>(
+    gapi: &S,
+    params: &PipelinesQueryArgs,
+) -> Result<Vec<PipelineSummary>, FetchError> {
+    let mut pipelines = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let amount = match params.pipeline_count {
+            Some(count) => (count - pipelines.len() as i64).min(PAGE_SIZE),
+            None => PAGE_SIZE,
+        };
+        let variables = <BranchPipelinesQuery as GraphQLQuery>::Variables {
+            project: params.project.clone(),
+            branch: params.git_ref.clone(),
+            status: params.pipeline_status.clone(),
+            amount: Some(amount),
+            after: after.take(),
+        };
+        let query = BranchPipelinesQuery::build_query(variables);
+        let resp: <BranchPipelinesQuery as GraphQLQuery>::ResponseData = gapi
+            .query::<BranchPipelinesQuery>(&query)
+            .await
+            .map_err(|err| FetchError::request("pipelines", err))?;
+
+        let Some(project) = resp.project else {
+            // fine if it became accessible in the meantime
+            find_project(gapi, &params.project).await?;
+            break;
+        };
+        let Some(page) = project.pipelines else {
+            break;
+        };
+        pipelines.extend(page.nodes.into_iter().flatten().flatten().map(summary));
+
+        // only one page without a count, there is no point in fetching all pipelines
+        let complete = params
+            .pipeline_count
+            .is_none_or(|count| pipelines.len() as i64 >= count);
+        if complete || !page.page_info.has_next_page {
+            break;
+        }
+        after = page.page_info.end_cursor;
+    }
+
+    Ok(pipelines)
+}
+
+fn summary(
+    p: branch_pipelines_query::BranchPipelinesQueryProjectPipelinesNodes,
+) -> PipelineSummary {
+    PipelineSummary {
+        id: p.id,
+        iid: p.iid,
+        sha: p.sha,
+        git_ref: p.ref_.unwrap_or("no name".to_string()),
+        author: p.user.map(|u| u.username).unwrap_or("unknown".to_string()),
+        created_at: p.created_at,
+        duration: p.duration,
+        status: p.status,
+    }
+}
+
+/// Query complexity a request of [`batched_pipelines`] is kept below. GitLab
+/// allows 250 but the estimate of [`complexity`] is a rough one.
+const MAX_COMPLEXITY: f64 = 200.0;
+
+/// Fields of [`BATCH_FIELDS`] outside of the pipelines connection, the
+/// project itself included.
+const PROJECT_FIELDS: f64 = 3.0;
+
+/// Fields of [`BATCH_FIELDS`] within the pipelines connection.
+const PIPELINE_FIELDS: f64 = 14.0;
+
+/// The fields of a project in a batch, the same as of [`BranchPipelinesQuery`]
+/// so the response of each project can be read the same way.
+const BATCH_FIELDS: &str = "
+    fullPath
+    pipelines(first: $amount{i}, ref: $branch{i}, status: $status{i}) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        id iid sha name ref status duration createdAt
+        user { username }
+      }
+    }";
+
+/// Pipelines of several projects in one request: the document has one
+/// aliased `p0: project(fullPath: $project0) { ... }` per project.
+struct BatchedPipelinesQuery;
+
+impl GraphQLQuery for BatchedPipelinesQuery {
+    /// `project0`, `branch0`, `status0`, `amount0`, `project1`, ...
+    type Variables = serde_json::Map<String, serde_json::Value>;
+    /// the projects by alias
+    type ResponseData =
+        HashMap<String, Option<branch_pipelines_query::BranchPipelinesQueryProject>>;
+
+    fn build_query(variables: Self::Variables) -> QueryBody<Self::Variables> {
+        let size = variables.keys().filter(|k| k.starts_with("project")).count();
+        QueryBody {
+            query: batch_documents()[size - 1],
+            variables,
+            operation_name: "BatchedPipelinesQuery",
+        }
+    }
+}
+
+/// Estimated complexity of asking for the pipelines of `params` in a batch.
+/// Like GitLab, every field counts once and a connection adds 1% of the
+/// complexity of its fields per node asked for.
+fn complexity(params: &PipelinesQueryArgs) -> f64 {
+    let amount = params.pipeline_count.unwrap_or(PAGE_SIZE).min(PAGE_SIZE);
+    PROJECT_FIELDS + PIPELINE_FIELDS * (1.0 + amount as f64 / 100.0)
+}
+
+/// The most projects a batch can hold, each of them asking for one pipeline.
+fn max_batch_size() -> usize {
+    let smallest = complexity(&PipelinesQueryArgs::new(String::new()).with_count(1));
+    (MAX_COMPLEXITY / smallest) as usize
+}
+
+/// Splits `params` into consecutive batches below [`MAX_COMPLEXITY`]; every
+/// batch holds at least one project.
+fn batches(params: &[PipelinesQueryArgs]) -> Vec<&[PipelinesQueryArgs]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut total = 0.0;
+    for (i, p) in params.iter().enumerate() {
+        let estimate = complexity(p);
+        let full = total + estimate > MAX_COMPLEXITY || i - start == max_batch_size();
+        if i > start && full {
+            batches.push(&params[start..i]);
+            start = i;
+            total = 0.0;
+        }
+        total += estimate;
+    }
+    if start < params.len() {
+        batches.push(&params[start..]);
+    }
+    batches
+}
+
+/// The documents for batches of 1 to [`max_batch_size`] projects, built once.
+fn batch_documents() -> &'static [&'static str] {
+    static DOCUMENTS: OnceLock<Vec<&'static str>> = OnceLock::new();
+    DOCUMENTS.get_or_init(|| {
+        (1..=max_batch_size())
+            .map(|size| &*Box::leak(batch_document(size).into_boxed_str()))
+            .collect()
+    })
+}
+
+fn batch_document(size: usize) -> String {
+    let variables: Vec<String> = (0..size)
+        .map(|i| {
+            format!("$project{i}: ID!, $branch{i}: String, ")
+                + &format!("$status{i}: PipelineStatusEnum, $amount{i}: Int")
+        })
+        .collect();
+    let projects: Vec<String> = (0..size)
+        .map(|i| {
+            let fields = BATCH_FIELDS.replace("{i}", &i.to_string());
+            format!("  p{i}: project(fullPath: $project{i}) {{{fields}\n  }}")
+        })
+        .collect();
+    format!(
+        "query BatchedPipelinesQuery({}) {{\n{}\n}}",
+        variables.join(", "),
+        projects.join("\n")
+    )
+}
+
+/// Polls the pipelines of all `params` with one request per batch of projects,
+/// see [`batches`], instead of one per project. Projects asking for more
+/// pipelines than fit on a page are fetched on their own. A failed request
+/// fails the projects of its batch only, they are asked for again on the next
+/// poll.
+pub(crate) fn batched_pipelines<S: Source>(
+    gapi: S,
+    params: Vec<PipelinesQueryArgs>,
+    mut schedule: Schedule,
+) -> Receiver<Vec<Result<BranchPipelineUpdate, FetchError>>> {
+    let (sender, receiver) = channel(1);
+    for p in &params {
+        schedule.watch(&p.project, Kind::Pipeline);
+    }
+
+    tokio::spawn(async move {
+        // there is no point in asking again for a missing project
+        let mut missing: HashMap<String, NotFound> = HashMap::new();
+        loop {
+            let fetched_at = chrono::Utc::now();
+            let skipped: Vec<bool> = params
+                .iter()
+                .map(|p| missing.contains_key(&p.project))
+                .collect();
+            let present: Vec<PipelinesQueryArgs> = params
+                .iter()
+                .zip(&skipped)
+                .filter(|(_, skipped)| !**skipped)
+                .map(|(p, _)| p.clone())
                 .collect();
+            let mut fetched = Vec::with_capacity(present.len());
+            for batch in batches(&present) {
+                fetched.extend(fetch_batch(&gapi, batch, fetched_at).await);
+            }
+            for update in &fetched {
+                if let Err(FetchError::NotFound(not_found)) = update {
+                    missing.insert(not_found.project.clone(), not_found.clone());
+                }
+            }
 
-            sender
-                .send(BranchPipelineUpdate {
-                    project: params.project.clone(),
-                    branch: params.git_ref.clone(),
-                    states,
+            let mut fetched = fetched.into_iter();
+            let updates = params
+                .iter()
+                .zip(skipped)
+                .map(|(p, skipped)| match skipped {
+                    true => Err(FetchError::NotFound(missing[&p.project].clone())),
+                    false => fetched.next().expect("an update per fetched project"),
                 })
-                .await
-                .unwrap();
+                .collect();
+            // the view is gone, nobody is interested in updates anymore
+            if sender.send(updates).await.is_err() {
+                break;
+            }
 
-            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            schedule.wait().await;
         }
     });
 
     return receiver;
 }
+
+async fn fetch_batch<S: Source>(
+    gapi: &S,
+    batch: &[PipelinesQueryArgs],
+    fetched_at: chrono::DateTime<chrono::Utc>,
+) -> Vec<Result<BranchPipelineUpdate, FetchError>> {
+    let mut variables = serde_json::Map::new();
+    for (i, params) in batch.iter().enumerate() {
+        let amount = params.pipeline_count.unwrap_or(PAGE_SIZE).min(PAGE_SIZE);
+        variables.insert(format!("project{i}"), params.project.clone().into());
+        variables.insert(format!("branch{i}"), params.git_ref.clone().into());
+        variables.insert(
+            format!("status{i}"),
+            serde_json::to_value(&params.pipeline_status).expect("status is serializable"),
+        );
+        variables.insert(format!("amount{i}"), amount.into());
+    }
+    let query = BatchedPipelinesQuery::build_query(variables);
+    let mut resp = match gapi.query::<BatchedPipelinesQuery>(&query).await {
+        Ok(resp) => resp,
+        Err(err) => {
+            let err = FetchError::request("pipelines", err);
+            return batch.iter().map(|_| Err(err.clone())).collect();
+        }
+    };
+
+    let mut updates = Vec::with_capacity(batch.len());
+    for (i, params) in batch.iter().enumerate() {
+        let Some(project) = resp.remove(&format!("p{i}")).flatten() else {
+            let found = find_project(gapi, &params.project).await;
+            updates.push(found.map(|_| BranchPipelineUpdate {
+                project: params.project.clone(),
+                branch: params.git_ref.clone(),
+                pipelines: Vec::new(),
+                fetched_at,
+            }));
+            continue;
+        };
+        let more = params.pipeline_count.is_some_and(|count| count > PAGE_SIZE);
+        let pipelines = match project.pipelines {
+            Some(page) if more && page.page_info.has_next_page => {
+                fetch_pipelines(gapi, params).await
+            }
+            Some(page) => {
+                let summaries = page.nodes.into_iter().flatten().flatten().map(summary);
+                Ok(summaries.collect())
+            }
+            None => Ok(Vec::new()),
+        };
+        updates.push(pipelines.map(|pipelines| BranchPipelineUpdate {
+            project: params.project.clone(),
+            branch: params.git_ref.clone(),
+            pipelines,
+            fetched_at,
+        }));
+    }
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn builds_aliased_document() {
+        let document = batch_document(2);
+        assert!(document.starts_with("query BatchedPipelinesQuery($project0: ID!"));
+        assert!(document.contains("p1: project(fullPath: $project1)"));
+        assert!(document.contains("pipelines(first: $amount1, ref: $branch1, status: $status1)"));
+    }
+
+    #[tokio::test]
+    async fn fetches_projects_in_one_request() {
+        let params = vec![
+            PipelinesQueryArgs::new("group/project".to_string()).with_count(30),
+            PipelinesQueryArgs::new("group/docs".to_string()).with_reference("main".to_string()),
+        ];
+        let (_controller, schedule) = crate::fetchers::poll::control();
+        let updates = batched_pipelines(testing::fixtures(), params, schedule)
+            .recv()
+            .await
+            .expect("an update");
+
+        let updates: Vec<_> = updates.into_iter().map(|u| u.expect("existing project")).collect();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].project, "group/project");
+        assert_eq!(updates[0].pipelines.len(), 2);
+        assert_eq!(updates[1].branch.as_deref(), Some("main"));
+        assert!(updates[1].pipelines.is_empty());
+    }
+
+    #[test]
+    fn splits_batches_by_complexity() {
+        let params = |count| -> Vec<PipelinesQueryArgs> {
+            (0..20)
+                .map(|i| PipelinesQueryArgs::new(format!("group/p{i}")).with_count(count))
+                .collect()
+        };
+        let small = params(10);
+        let large = params(100);
+
+        for all in [&small, &large] {
+            let split = batches(all);
+            assert_eq!(split.iter().map(|b| b.len()).sum::<usize>(), all.len());
+            for batch in &split {
+                assert!(batch.len() <= max_batch_size());
+                assert!(batch.iter().map(complexity).sum::<f64>() <= MAX_COMPLEXITY);
+            }
+        }
+        assert!(batches(&large).len() > batches(&small).len());
+    }
+
+    #[tokio::test]
+    async fn fails_the_projects_of_a_failed_batch() {
+        let params = vec![PipelinesQueryArgs::new("group/broken".to_string())];
+        let (_controller, schedule) = crate::fetchers::poll::control();
+        let updates = batched_pipelines(testing::fixtures(), params, schedule)
+            .recv()
+            .await
+            .expect("an update");
+
+        assert!(matches!(updates[..], [Err(FetchError::Transient(_))]));
+    }
+
+    #[tokio::test]
+    async fn keeps_polling_after_a_failed_request() {
+        let params = PipelinesQueryArgs::new("group/broken".to_string());
+        let (controller, schedule) = crate::fetchers::poll::control();
+        let mut receiver = branch_pipelines(testing::fixtures(), params, schedule);
+
+        let update = receiver.recv().await.expect("an update");
+        assert!(matches!(update, Err(FetchError::Transient(_))));
+        controller.refresh();
+        let update = receiver.recv().await.expect("another update");
+        assert!(matches!(update, Err(FetchError::Transient(_))));
+    }
+}
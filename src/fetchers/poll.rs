@@ -0,0 +1,207 @@
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::webhook::Push;
+
+/// Time between two polls of a fetcher.
+pub(crate) const INTERVAL: Duration = Duration::from_secs(1);
+/// Time between two polls of a fetcher whose project sent no webhook event yet
+/// while serving them, e.g. an expanded downstream project without webhooks.
+const SLOW_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What changed in a project, a fetcher watches the kinds its data depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Pipeline,
+    Job,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Control {
+    /// poll right away, even if paused
+    Refresh,
+    Pause,
+    Resume,
+}
+
+/// Held by a view to control the polling of its fetcher.
+pub struct Controller {
+    sender: Sender<Control>,
+    paused: bool,
+}
+
+/// Held by the fetcher task to wait for its next poll.
+pub struct Schedule {
+    /// `None` once the view is gone
+    receiver: Option<Receiver<Control>>,
+    paused: bool,
+    /// webhook events replacing the interval, see [`crate::webhook::serve`]
+    pushes: Option<broadcast::Receiver<Push>>,
+    /// the events of interest to the fetcher, see [`Schedule::watch`]
+    watched: HashSet<(String, Kind)>,
+    /// the watched projects which sent an event, they have webhooks
+    heard: HashSet<String>,
+}
+
+pub fn control() -> (Controller, Schedule) {
+    let (sender, receiver) = channel(8);
+    (
+        Controller {
+            sender,
+            paused: false,
+        },
+        Schedule {
+            receiver: Some(receiver),
+            paused: false,
+            pushes: crate::webhook::subscribe(),
+            watched: HashSet::new(),
+            heard: HashSet::new(),
+        },
+    )
+}
+
+impl Controller {
+    pub fn refresh(&self) {
+        // a full queue triggers a poll anyway, a missing fetcher (e.g. in a
+        // replay) can not be controlled
+        _ = self.sender.try_send(Control::Refresh);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        let control = match self.paused {
+            true => Control::Pause,
+            false => Control::Resume,
+        };
+        _ = self.sender.try_send(control);
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+}
+
+impl Schedule {
+    /// Lets webhook events of `kind` for `project` end the wait, the events of
+    /// the projects and kinds not watched are ignored.
+    pub fn watch(&mut self, project: &str, kind: Kind) {
+        self.watched.insert((project.to_string(), kind));
+    }
+
+    /// Waits until the next poll is due: after the interval (or the next
+    /// webhook event while serving them, at the latest after the slow interval
+    /// for projects without events) or right away on a refresh. While paused
+    /// only a refresh or the resume ends the wait.
+    pub async fn wait(&mut self) {
+        loop {
+            let Some(receiver) = self.receiver.as_mut() else {
+                due(&mut self.pushes, &self.watched, &mut self.heard).await;
+                return;
+            };
+
+            let control = match self.paused {
+                true => receiver.recv().await,
+                false => tokio::select! {
+                    _ = due(&mut self.pushes, &self.watched, &mut self.heard) => return,
+                    control = receiver.recv() => control,
+                },
+            };
+            match control {
+                Some(Control::Refresh) => return,
+                Some(Control::Pause) => self.paused = true,
+                Some(Control::Resume) => {
+                    self.paused = false;
+                    return;
+                }
+                None => self.receiver = None,
+            }
+        }
+    }
+}
+
+async fn due(
+    pushes: &mut Option<broadcast::Receiver<Push>>,
+    watched: &HashSet<(String, Kind)>,
+    heard: &mut HashSet<String>,
+) {
+    let Some(receiver) = pushes.as_mut() else {
+        tokio::time::sleep(INTERVAL).await;
+        return;
+    };
+    let unheard = watched.iter().any(|(project, _)| !heard.contains(project));
+    let slow = async {
+        match unheard {
+            true => tokio::time::sleep(SLOW_INTERVAL).await,
+            false => std::future::pending().await,
+        }
+    };
+    let closed = tokio::select! {
+        closed = pushed(receiver, watched, heard) => closed,
+        _ = slow => false,
+    };
+    if closed {
+        *pushes = None;
+    }
+}
+
+/// Waits for a watched event, `true` once there are no more events.
+async fn pushed(
+    receiver: &mut broadcast::Receiver<Push>,
+    watched: &HashSet<(String, Kind)>,
+    heard: &mut HashSet<String>,
+) -> bool {
+    loop {
+        match receiver.recv().await {
+            Ok(push) => {
+                if watched.iter().any(|(project, _)| *project == push.project) {
+                    heard.insert(push.project.clone());
+                }
+                if watched.contains(&(push.project, push.kind)) {
+                    return false;
+                }
+            }
+            // missed events are no reason to wait for another one
+            Err(broadcast::error::RecvError::Lagged(_)) => return false,
+            Err(broadcast::error::RecvError::Closed) => return true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_watched_pushes() {
+        let (sender, pushes) = broadcast::channel(8);
+        let mut schedule = Schedule {
+            receiver: None,
+            paused: false,
+            pushes: Some(pushes),
+            watched: HashSet::new(),
+            heard: HashSet::new(),
+        };
+        schedule.watch("group/project", Kind::Pipeline);
+        let push = |project: &str, kind| Push {
+            project: project.to_string(),
+            kind,
+        };
+
+        sender
+            .send(push("group/other", Kind::Pipeline))
+            .expect("a subscriber");
+        sender
+            .send(push("group/project", Kind::Job))
+            .expect("a subscriber");
+        let wait = tokio::time::timeout(INTERVAL * 2, schedule.wait()).await;
+        assert!(wait.is_err(), "woken by an event which is not watched");
+
+        sender
+            .send(push("group/project", Kind::Pipeline))
+            .expect("a subscriber");
+        let wait = tokio::time::timeout(INTERVAL * 2, schedule.wait()).await;
+        assert!(wait.is_ok());
+    }
+}
@@ -0,0 +1,220 @@
+use graphql_client::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::fetchers::source::Source;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/projects.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct ProjectSearchQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/member_projects.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct MemberProjectsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/branches.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct BranchNamesQuery;
+
+/// Upper bound of branches offered for a project.
+const BRANCH_LIMIT: i64 = 1000;
+
+/// Number of suggestions offered for a project which was not found.
+const SUGGESTIONS: usize = 3;
+
+/// A project which does not exist or which the token has no access to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotFound {
+    pub project: String,
+    /// similar projects the user is a member of, the most similar one first
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "project {} was not found or is not accessible", self.project)?;
+        if !self.suggestions.is_empty() {
+            write!(f, ", did you mean {}?", self.suggestions.join(" or "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a fetcher has no update for a project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FetchError {
+    NotFound(NotFound),
+    /// the request failed, e.g. as GitLab is not reachable; it is sent again
+    /// on the next poll
+    Transient(String),
+}
+
+impl FetchError {
+    /// A failed request for `what`, e.g. `pipelines`.
+    pub fn request(what: &str, err: impl fmt::Display) -> Self {
+        FetchError::Transient(format!("fetching {} failed: {}", what, err))
+    }
+}
+
+impl From<NotFound> for FetchError {
+    fn from(missing: NotFound) -> Self {
+        FetchError::NotFound(missing)
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::NotFound(missing) => missing.fmt(f),
+            FetchError::Transient(err) => f.write_str(err),
+        }
+    }
+}
+
+/// Checks that `project` is accessible, suggesting similar projects if not.
+pub(crate) async fn find_project<S: Source>(gapi: &S, project: &str) -> Result<(), FetchError> {
+    // GitLab searches for substrings of at least 3 characters, a prefix of the
+    // name finds the project despite a typo later on
+    let name = project.rsplit('/').next().unwrap_or(project);
+    let search: String = name.chars().take(3).collect();
+    let variables = <ProjectSearchQuery as GraphQLQuery>::Variables {
+        project: project.to_string(),
+        search,
+    };
+    let query = ProjectSearchQuery::build_query(variables);
+    let resp = gapi
+        .query::<ProjectSearchQuery>(&query)
+        .await
+        .map_err(|err| FetchError::request("the project", err))?;
+    if resp.project.is_some() {
+        return Ok(());
+    }
+
+    let candidates = resp
+        .projects
+        .into_iter()
+        .flat_map(|p| p.nodes)
+        .flatten()
+        .flatten()
+        .map(|p| p.full_path);
+    Err(FetchError::NotFound(NotFound {
+        project: project.to_string(),
+        suggestions: suggestions(project, candidates),
+    }))
+}
+
+/// Paths of all projects the user is a member of.
+pub(crate) async fn member_projects<S: Source>(gapi: &S) -> Result<Vec<String>, FetchError> {
+    let mut projects = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let variables = <MemberProjectsQuery as GraphQLQuery>::Variables { after: after.take() };
+        let query = MemberProjectsQuery::build_query(variables);
+        let resp = gapi
+            .query::<MemberProjectsQuery>(&query)
+            .await
+            .map_err(|err| FetchError::request("your projects", err))?;
+
+        let Some(page) = resp.projects else {
+            break;
+        };
+        projects.extend(page.nodes.into_iter().flatten().flatten().map(|p| p.full_path));
+        if !page.page_info.has_next_page {
+            break;
+        }
+        after = page.page_info.end_cursor;
+    }
+    Ok(projects)
+}
+
+/// Names of the branches of `project`, the default branch first.
+pub(crate) async fn branches<S: Source>(
+    gapi: &S,
+    project: &str,
+) -> Result<Vec<String>, FetchError> {
+    let variables = <BranchNamesQuery as GraphQLQuery>::Variables {
+        project: project.to_string(),
+        limit: BRANCH_LIMIT,
+    };
+    let query = BranchNamesQuery::build_query(variables);
+    let resp = gapi
+        .query::<BranchNamesQuery>(&query)
+        .await
+        .map_err(|err| FetchError::request("the branches", err))?;
+
+    let Some(repository) = resp.project.and_then(|p| p.repository) else {
+        return Ok(Vec::new());
+    };
+    let mut branches: Vec<String> = repository.branch_names.into_iter().flatten().collect();
+    branches.sort();
+    if let Some(root) = repository.root_ref {
+        branches.retain(|b| *b != root);
+        branches.insert(0, root);
+    }
+    Ok(branches)
+}
+
+/// The candidates closest to `project`, leaving out those which hardly
+/// resemble it.
+fn suggestions(project: &str, candidates: impl Iterator<Item = String>) -> Vec<String> {
+    let limit = (project.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, String)> = candidates
+        .map(|c| (distance(project, &c), c))
+        .filter(|(d, _)| *d <= limit)
+        .collect();
+    scored.sort();
+    scored.into_iter().take(SUGGESTIONS).map(|(_, c)| c).collect()
+}
+
+/// Levenshtein distance of `a` and `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = match ca == *cb {
+                true => diagonal,
+                false => 1 + diagonal.min(above).min(row[j]),
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_similar_projects() {
+        let candidates = ["group/projects", "group/docs", "other/projects", "group/project"];
+        let found = suggestions("group/projetc", candidates.iter().map(|c| c.to_string()));
+        assert_eq!(found, vec!["group/project", "group/projects"]);
+    }
+
+    #[test]
+    fn measures_distance() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::OnceLock;
+
+use gitlab::api;
+
+pub type ApiError = api::ApiError<<gitlab::AsyncGitlab as api::RestClient>::Error>;
+
+/// Client and token for the requests `api::raw` of the gitlab crate can not
+/// make: it reads every body into memory first and sends no custom headers.
+static CLIENT: OnceLock<(reqwest::Client, String)> = OnceLock::new();
+
+pub fn init(token: &str) {
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()
+        .expect("the tls backend is available");
+    if CLIENT.set((client, token.to_string())).is_err() {
+        panic!("rest client initialized twice");
+    }
+}
+
+/// A GET request of `url` (see [`api::RestClient::rest_endpoint`]) with the token.
+pub(crate) fn get(url: &str) -> reqwest::RequestBuilder {
+    let (client, token) = CLIENT.get().expect("rest client is initialized");
+    client.get(url).header("PRIVATE-TOKEN", token)
+}
+
+/// Percent encodes the few characters which are valid in project paths, refs
+/// and file names but have a meaning in urls.
+pub(crate) fn escape(value: &str, keep_slashes: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '/' if keep_slashes => escaped.push(c),
+            '/' | '%' | '?' | '#' | ' ' | '&' | '+' => {
+                escaped.push_str(&format!("%{:02X}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether GitLab refused the token itself (401 or 403) rather than the
+/// request failing for another reason, e.g. the network or a 5xx.
+pub(crate) fn rejected(err: &ApiError) -> bool {
+    let status = match err {
+        api::ApiError::GitlabWithStatus { status, .. }
+        | api::ApiError::GitlabObjectWithStatus { status, .. }
+        | api::ApiError::GitlabUnrecognizedWithStatus { status, .. }
+        | api::ApiError::GitlabService { status, .. } => *status,
+        _ => return false,
+    };
+    matches!(
+        status,
+        http::StatusCode::UNAUTHORIZED | http::StatusCode::FORBIDDEN
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_only_refused_tokens() {
+        let error = |status| ApiError::GitlabWithStatus {
+            status,
+            msg: "failed".to_string(),
+        };
+        assert!(rejected(&error(http::StatusCode::UNAUTHORIZED)));
+        assert!(rejected(&error(http::StatusCode::FORBIDDEN)));
+        assert!(!rejected(&error(http::StatusCode::BAD_GATEWAY)));
+    }
+}
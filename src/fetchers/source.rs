@@ -0,0 +1,33 @@
+use graphql_client::{GraphQLQuery, QueryBody};
+use std::fmt::Debug;
+use std::future::Future;
+
+/// Where the GraphQL fetchers get their data from: GitLab itself or, e.g. in
+/// tests, a set of recorded responses. Queries to GitLab go through the
+/// [`store`](super::store), so identical queries of several views are sent once.
+pub trait Source: Clone + Send + Sync + 'static {
+    fn query<Q>(
+        &self,
+        query: &QueryBody<Q::Variables>,
+    ) -> impl Future<Output = Result<Q::ResponseData, gitlab::GitlabError>> + Send
+    where
+        Q: GraphQLQuery,
+        Q::Variables: Debug + Sync,
+        Q::ResponseData: Clone + Send + Sync + 'static;
+}
+
+impl Source for gitlab::AsyncGitlab {
+    fn query<Q>(
+        &self,
+        query: &QueryBody<Q::Variables>,
+    ) -> impl Future<Output = Result<Q::ResponseData, gitlab::GitlabError>> + Send
+    where
+        Q: GraphQLQuery,
+        Q::Variables: Debug + Sync,
+        Q::ResponseData: Clone + Send + Sync + 'static,
+    {
+        let variables = serde_json::to_string(&query.variables).expect("variables are serializable");
+        let key = (query.operation_name.to_string(), variables);
+        super::store::current().get(key, self.graphql::<Q>(query))
+    }
+}
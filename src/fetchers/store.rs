@@ -0,0 +1,119 @@
+//! Responses of GitLab shared between all views of this process. Views polling
+//! the same query, e.g. a dashboard and the pipelines opened from it, get the
+//! response of one request instead of asking GitLab each.
+//!
+//! Every view still polls on its own schedule, there is no central poller
+//! fanning out the responses. A response is only shared while it is younger
+//! than the ttl, so views polling out of step by more than that each fetch.
+use std::any::Any;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+/// The last response of a query and when it was received.
+type Slot = Arc<tokio::sync::Mutex<Option<(Instant, Arc<dyn Any + Send + Sync>)>>>;
+
+pub struct Store {
+    /// responses younger than this are answered from the store
+    ttl: Duration,
+    /// per operation and variables
+    slots: Mutex<HashMap<(String, String), Slot>>,
+}
+
+impl Store {
+    pub fn new(ttl: Duration) -> Self {
+        Store {
+            ttl,
+            slots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The stored response for `key` or the one of `fetch`. Concurrent calls
+    /// for the same key wait for the first one instead of fetching as well.
+    pub async fn get<T, E>(
+        &self,
+        key: (String, String),
+        fetch: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E>
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        let slot = {
+            let mut slots = self.slots.lock().expect("store lock");
+            self.evict(&mut slots);
+            slots.entry(key).or_default().clone()
+        };
+
+        let mut stored = slot.lock().await;
+        if let Some((_, response)) = stored.as_ref().filter(|(at, _)| at.elapsed() < self.ttl) {
+            if let Some(response) = response.downcast_ref::<T>() {
+                return Ok(response.clone());
+            }
+        }
+
+        // a failed fetch is not stored, the next caller tries again
+        let response = fetch.await?;
+        *stored = Some((Instant::now(), Arc::new(response.clone())));
+        Ok(response)
+    }
+
+    /// Drops the expired slots nobody is fetching or waiting for, views which
+    /// are gone would leave their last responses behind otherwise.
+    fn evict(&self, slots: &mut HashMap<(String, String), Slot>) {
+        slots.retain(|_, slot| {
+            let Ok(stored) = slot.try_lock() else {
+                return true;
+            };
+            Arc::strong_count(slot) > 1
+                || stored.as_ref().is_some_and(|(at, _)| at.elapsed() < self.ttl)
+        });
+    }
+}
+
+/// The store of this process, responses live as long as a poll interval.
+pub fn current() -> &'static Store {
+    STORE.get_or_init(|| Store::new(crate::fetchers::poll::INTERVAL))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn fetches_once_per_ttl() {
+        let store = Store::new(Duration::from_secs(60));
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok::<_, ()>(42)
+        };
+        let key = || ("Query".to_string(), "{}".to_string());
+
+        let (a, b) = tokio::join!(store.get(key(), fetch()), store.get(key(), fetch()));
+        assert_eq!((a, b), (Ok(42), Ok(42)));
+        assert_eq!(store.get(key(), fetch()).await, Ok(42));
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        let other = ("Query".to_string(), r#"{"project":"other"}"#.to_string());
+        assert_eq!(store.get(other, fetch()).await, Ok(42));
+        assert_eq!(fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn evicts_expired_responses() {
+        let store = Store::new(Duration::from_millis(10));
+        let key = |project: &str| ("Query".to_string(), project.to_string());
+
+        assert_eq!(store.get(key("old"), async { Ok::<_, ()>(1) }).await, Ok(1));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(store.get(key("new"), async { Ok::<_, ()>(2) }).await, Ok(2));
+
+        let slots = store.slots.lock().expect("store lock");
+        assert_eq!(slots.keys().collect::<Vec<_>>(), vec![&key("new")]);
+    }
+}
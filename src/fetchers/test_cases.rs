@@ -0,0 +1,141 @@
+use graphql_client::*;
+use tokio::sync::mpsc::{channel, Receiver};
+
+use crate::fetchers::source::Source;
+
+pub use crate::fetchers::test_cases::test_suite_query::TestCaseStatus;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    query_path = "graphql/test_cases.graphql",
+    schema_path = "graphql/schema.json",
+    variables_derives = "Debug",
+    response_derives = "Deserialize,Serialize,PartialEq,Debug,Clone"
+)]
+struct TestSuiteQuery;
+
+pub struct TestCase {
+    pub suite: String,
+    pub name: String,
+    pub classname: Option<String>,
+    pub file: Option<String>,
+    pub status: TestCaseStatus,
+    /// output of the test, usually containing the failure message
+    pub output: Option<String>,
+}
+
+/// A test suite of a pipeline identified by the jobs which reported it.
+pub struct SuiteQueryArgs {
+    pub project: String,
+    pub pipeline_iid: String,
+    pub build_ids: Vec<String>,
+}
+
+/// Failed (and errored) test cases of the given test suites.
+/// The test cases are only fetched once; a failed request fails them all, as
+/// an incomplete list would hide failed tests.
+pub(crate) fn failed_tests<S: Source>(
+    gapi: S,
+    suites: Vec<SuiteQueryArgs>,
+) -> Receiver<Result<Vec<TestCase>, String>> {
+    let (sender, receiver) = channel(1);
+
+    tokio::spawn(async move {
+        let mut failed = Vec::new();
+        for suite in suites {
+            match failed_cases(&gapi, suite).await {
+                Ok(cases) => failed.extend(cases),
+                Err(err) => {
+                    _ = sender.send(Err(err)).await;
+                    return;
+                }
+            }
+        }
+
+        _ = sender.send(Ok(failed)).await;
+    });
+
+    return receiver;
+}
+
+/// Follows the pages of the test cases of `suite`, the failed ones are only
+/// known once all of them are fetched.
+async fn failed_cases<S: Source>(gapi: &S, suite: SuiteQueryArgs) -> Result<Vec<TestCase>, String> {
+    let mut failed = Vec::new();
+    let mut after: Option<String> = None;
+
+    loop {
+        let variables = <TestSuiteQuery as GraphQLQuery>::Variables {
+            project: suite.project.clone(),
+            iid: suite.pipeline_iid.clone(),
+            build_ids: suite.build_ids.clone(),
+            after: after.take(),
+        };
+        let query = TestSuiteQuery::build_query(variables);
+        let resp = gapi
+            .query::<TestSuiteQuery>(&query)
+            .await
+            .map_err(|err| format!("fetching the failed tests failed: {}", err))?;
+
+        let Some(test_suite) = resp
+            .project
+            .and_then(|p| p.pipeline)
+            .and_then(|p| p.test_suite)
+        else {
+            return Ok(failed);
+        };
+        let suite_name = test_suite.name.unwrap_or("no name".to_string());
+        let Some(page) = test_suite.test_cases else {
+            return Ok(failed);
+        };
+
+        failed.extend(
+            page.nodes
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter(|c| {
+                    matches!(
+                        c.status,
+                        Some(TestCaseStatus::failed) | Some(TestCaseStatus::error)
+                    )
+                })
+                .map(|c| TestCase {
+                    suite: suite_name.clone(),
+                    name: c.name.unwrap_or("no name".to_string()),
+                    classname: c.classname,
+                    file: c.file,
+                    status: c.status.unwrap_or(TestCaseStatus::failed),
+                    output: c.system_output,
+                }),
+        );
+
+        match page.page_info.end_cursor {
+            Some(cursor) if page.page_info.has_next_page => after = Some(cursor),
+            _ => return Ok(failed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[tokio::test]
+    async fn fetches_failed_cases_of_every_page() {
+        let suites = vec![SuiteQueryArgs {
+            project: "group/project".to_string(),
+            pipeline_iid: "53".to_string(),
+            build_ids: vec!["2002".to_string()],
+        }];
+        let failed = failed_tests(testing::fixtures(), suites)
+            .recv()
+            .await
+            .expect("an update")
+            .expect("the failed tests");
+
+        let names: Vec<_> = failed.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["parses_config", "ranks_matches"]);
+    }
+}
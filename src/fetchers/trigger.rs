@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use gitlab::api::{AsyncQuery, BodyError, Endpoint};
+use http::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::fetchers::rest::{escape, ApiError};
+
+#[derive(Debug, Serialize)]
+struct Variable<'a> {
+    key: &'a str,
+    value: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct CreatePipelineBody<'a> {
+    #[serde(rename = "ref")]
+    git_ref: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    variables: Vec<Variable<'a>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    inputs: BTreeMap<&'a str, &'a str>,
+}
+
+/// Creation of a new pipeline for a ref.
+struct CreatePipeline<'a> {
+    project: &'a str,
+    body: CreatePipelineBody<'a>,
+}
+
+impl Endpoint for CreatePipeline<'_> {
+    fn method(&self) -> Method {
+        Method::POST
+    }
+
+    fn endpoint(&self) -> Cow<'static, str> {
+        format!("projects/{}/pipeline", escape(self.project, false)).into()
+    }
+
+    fn body(&self) -> Result<Option<(&'static str, Vec<u8>)>, BodyError> {
+        let body = serde_json::to_vec(&self.body)?;
+        Ok(Some(("application/json", body)))
+    }
+}
+
+/// The parts of the created pipeline required to follow it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedPipeline {
+    pub iid: u64,
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TriggerArgs {
+    project: String,
+    git_ref: String,
+    variables: Vec<(String, String)>,
+    inputs: Vec<(String, String)>,
+}
+
+impl TriggerArgs {
+    pub fn new(project: String, git_ref: String) -> Self {
+        return Self {
+            project,
+            git_ref,
+            ..Self::default()
+        };
+    }
+    pub fn with_variables(mut self, variables: Vec<(String, String)>) -> Self {
+        self.variables = variables;
+        self
+    }
+    pub fn with_inputs(mut self, inputs: Vec<(String, String)>) -> Self {
+        self.inputs = inputs;
+        self
+    }
+}
+
+/// Creates a new pipeline for the ref, optionally with CI variables and inputs.
+pub(crate) async fn create_pipeline(
+    gapi: &gitlab::AsyncGitlab,
+    params: &TriggerArgs,
+) -> Result<CreatedPipeline, ApiError> {
+    let endpoint = CreatePipeline {
+        project: &params.project,
+        body: CreatePipelineBody {
+            git_ref: &params.git_ref,
+            variables: params
+                .variables
+                .iter()
+                .map(|(key, value)| Variable { key, value })
+                .collect(),
+            inputs: params
+                .inputs
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect(),
+        },
+    };
+    endpoint.query_async(gapi).await
+}
@@ -15,3 +15,67 @@ impl From<&str> for GitlabRef {
         }
     }
 }
+
+impl std::fmt::Display for GitlabRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitlabRef::Repo(repo) => write!(f, "{}", repo),
+            GitlabRef::Branch(repo, branch) => write!(f, "{}@{}", repo, branch),
+        }
+    }
+}
+
+impl GitlabRef {
+    /// The project of the `origin` remote and the current branch of the git
+    /// checkout in the current directory, if it is one.
+    pub fn from_checkout() -> Option<GitlabRef> {
+        let project = project_path(&git(&["remote", "get-url", "origin"])?)?;
+        match git(&["rev-parse", "--abbrev-ref", "HEAD"]) {
+            // a detached head is on no branch
+            Some(branch) if branch != "HEAD" => Some(GitlabRef::Branch(project, branch)),
+            _ => Some(GitlabRef::Repo(project)),
+        }
+    }
+}
+
+/// The trimmed output of a successful git command.
+fn git(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// `group/project` of remote urls like `git@host:group/project.git`,
+/// `ssh://git@host:22/group/project.git` or `https://host/group/project`.
+fn project_path(url: &str) -> Option<String> {
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.split_once('/')?.1,
+        None => url.split_once(':')?.1,
+    };
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    match path.is_empty() {
+        true => None,
+        false => Some(path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_remote_urls() {
+        let expected = Some("group/sub/project".to_string());
+        assert_eq!(project_path("git@gitlab.com:group/sub/project.git"), expected);
+        assert_eq!(project_path("ssh://git@gitlab.com:2222/group/sub/project.git"), expected);
+        assert_eq!(project_path("https://gitlab.com/group/sub/project"), expected);
+        assert_eq!(project_path("https://gitlab.com/"), None);
+    }
+}
@@ -0,0 +1,30 @@
+/// Coarse human readable representation of an age like `5m` or `3d`.
+pub fn age(age: chrono::TimeDelta) -> String {
+    match age {
+        a if a.num_days() > 0 => format!("{}d", a.num_days()),
+        a if a.num_hours() > 0 => format!("{}h", a.num_hours()),
+        a if a.num_minutes() > 0 => format!("{}m", a.num_minutes()),
+        a => format!("{}s", a.num_seconds().max(0)),
+    }
+}
+
+/// Freshness of the shown data like `updated 3s ago`.
+pub fn updated(at: chrono::DateTime<chrono::Utc>, paused: bool) -> String {
+    let age = age(chrono::Utc::now().signed_duration_since(at));
+    match paused {
+        true => format!("paused, updated {} ago", age),
+        false => format!("updated {} ago", age),
+    }
+}
+
+/// Shown instead of [`updated`] while the data is the one cached on disk.
+pub const STALE: &str = "cached, loading ...";
+
+/// Human readable representation of a duration given in seconds like `4m 02s`.
+pub fn duration(seconds: i64) -> String {
+    match seconds {
+        s if s >= 3600 => format!("{}h {:02}m", s / 3600, (s % 3600) / 60),
+        s if s >= 60 => format!("{}m {:02}s", s / 60, s % 60),
+        s => format!("{}s", s),
+    }
+}
@@ -0,0 +1,313 @@
+//! Maps key presses to the actions of the views. The bindings start from a
+//! preset and can be overridden per action in the `[keys]` table of the config:
+//!
+//! ```toml
+//! [keys]
+//! preset = "vi"
+//! quit = ["q", "ctrl-d"]
+//! ```
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// close the current view
+    Quit,
+    /// fetch right away
+    Refresh,
+    /// stop or restart fetching
+    Pause,
+    Next,
+    Prev,
+    Up,
+    Down,
+    /// open the selection in a view of its own
+    Open,
+    /// run a new pipeline
+    Run,
+    /// retry the job
+    Retry,
+    /// cancel the job
+    Cancel,
+    /// expand or collapse a downstream pipeline
+    Toggle,
+    /// open the test report
+    Tests,
+    /// download the selected artifact
+    Download,
+    /// show or hide what the status symbols mean
+    ToggleLegend,
+    /// open the log of the job
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// arrow keys
+    #[default]
+    Default,
+    /// hjkl in addition to the arrow keys
+    Vi,
+    /// ctrl-f/b/n/p in addition to the arrow keys
+    Emacs,
+}
+
+/// The `[keys]` table of the config.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct KeysConfig {
+    #[serde(default)]
+    pub preset: Preset,
+    /// replaces the keys of the preset for these actions
+    #[serde(flatten)]
+    pub bindings: HashMap<Action, Vec<String>>,
+}
+
+type Key = (KeyCode, KeyModifiers);
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    actions: HashMap<Key, Action>,
+    /// the keys per action in the order they were configured, for hints
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Preset {
+    fn bindings(&self) -> Vec<(Action, &'static [&'static str])> {
+        let mut bindings = vec![
+            (Action::Quit, &["q", "esc"][..]),
+            (Action::Refresh, &["r"][..]),
+            (Action::Pause, &["p"][..]),
+            (Action::Next, &["right"][..]),
+            (Action::Prev, &["left"][..]),
+            (Action::Up, &["up"][..]),
+            (Action::Down, &["down"][..]),
+            (Action::Open, &["enter"][..]),
+            (Action::Run, &["n"][..]),
+            (Action::Retry, &["R"][..]),
+            (Action::Cancel, &["C"][..]),
+            (Action::Toggle, &["space"][..]),
+            (Action::Tests, &["t"][..]),
+            (Action::Download, &["d"][..]),
+            (Action::ToggleLegend, &["?"][..]),
+            (Action::Log, &["L"][..]),
+        ];
+        let overrides: Vec<(Action, &'static [&'static str])> = match self {
+            Preset::Default => vec![],
+            Preset::Vi => vec![
+                (Action::Next, &["l", "right"][..]),
+                (Action::Prev, &["h", "left"][..]),
+                (Action::Up, &["k", "up"][..]),
+                (Action::Down, &["j", "down"][..]),
+                (Action::Open, &["enter", "o"][..]),
+            ],
+            Preset::Emacs => vec![
+                (Action::Quit, &["ctrl-g", "q", "esc"][..]),
+                (Action::Next, &["ctrl-f", "right"][..]),
+                (Action::Prev, &["ctrl-b", "left"][..]),
+                (Action::Up, &["ctrl-p", "up"][..]),
+                (Action::Down, &["ctrl-n", "down"][..]),
+                (Action::Open, &["enter", "ctrl-j"][..]),
+            ],
+        };
+        for (action, keys) in overrides {
+            if let Some(binding) = bindings.iter_mut().find(|(a, _)| *a == action) {
+                binding.1 = keys;
+            }
+        }
+        bindings
+    }
+}
+
+impl Keymap {
+    pub fn new(config: &KeysConfig) -> Result<Self, String> {
+        let mut keys: HashMap<Action, Vec<Key>> = HashMap::new();
+        for (action, names) in config.preset.bindings() {
+            let parsed = names.iter().map(|n| parse_key(n)).collect::<Result<_, _>>()?;
+            keys.insert(action, parsed);
+        }
+        for (action, names) in &config.bindings {
+            let parsed = names.iter().map(|n| parse_key(n)).collect::<Result<_, _>>()?;
+            keys.insert(*action, parsed);
+        }
+
+        let mut actions = HashMap::new();
+        for (action, action_keys) in &keys {
+            for key in action_keys {
+                if let Some(other) = actions.insert(*key, *action) {
+                    if other != *action {
+                        return Err(format!(
+                            "{} is bound to both {:?} and {:?}",
+                            key_label(key),
+                            other,
+                            action
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(Keymap { actions, keys })
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.actions.get(&normalize(event.code, event.modifiers)).copied()
+    }
+
+    /// The first key of `action` like `d`, for hints in the views.
+    pub fn hint(&self, action: Action) -> String {
+        self.keys
+            .get(&action)
+            .and_then(|k| k.first())
+            .map(key_label)
+            .unwrap_or("unbound".to_string())
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&KeysConfig::default()).expect("valid default keys")
+    }
+}
+
+/// Sets the keymap of this process, must be called at most once.
+pub fn init(keymap: Keymap) {
+    if KEYMAP.set(keymap).is_err() {
+        panic!("keymap initialized twice");
+    }
+}
+
+pub fn current() -> &'static Keymap {
+    KEYMAP.get_or_init(Keymap::default)
+}
+
+/// The shift of upper case letters is part of the char, `shift-r` is `R`.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> Key {
+    let code = match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyCode::Char(c.to_ascii_uppercase())
+        }
+        _ => code,
+    };
+    match code {
+        KeyCode::Char(_) | KeyCode::BackTab => (code, modifiers.difference(KeyModifiers::SHIFT)),
+        _ => (code, modifiers),
+    }
+}
+
+/// Parses keys like `q`, `R`, `enter`, `ctrl-n` or `alt-left`.
+fn parse_key(name: &str) -> Result<Key, String> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    while let Some((modifier, tail)) = rest.split_once('-').filter(|(_, t)| !t.is_empty()) {
+        modifiers |= match modifier {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        rest = tail;
+    }
+
+    let code = match rest {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return Err(format!("unknown key {}", name)),
+            }
+        }
+    };
+    Ok(normalize(code, modifiers))
+}
+
+fn key_label((code, modifiers): &Key) -> String {
+    let key = match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        code => format!("{:?}", code).to_lowercase(),
+    };
+    let mut label = String::new();
+    if modifiers.contains(KeyModifiers::CONTROL) {
+        label.push_str("ctrl-");
+    }
+    if modifiers.contains(KeyModifiers::ALT) {
+        label.push_str("alt-");
+    }
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        label.push_str("shift-");
+    }
+    label + &key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(parse_key("q"), Ok((KeyCode::Char('q'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("R"), Ok((KeyCode::Char('R'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("-"), Ok((KeyCode::Char('-'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("ctrl-n"), Ok((KeyCode::Char('n'), KeyModifiers::CONTROL)));
+        assert_eq!(parse_key("alt-left"), Ok((KeyCode::Left, KeyModifiers::ALT)));
+        assert_eq!(parse_key("shift-r"), Ok((KeyCode::Char('R'), KeyModifiers::NONE)));
+        assert!(parse_key("hyper-x").is_err());
+    }
+
+    #[test]
+    fn overrides_preset() {
+        let config: KeysConfig = toml::from_str(
+            r#"
+            preset = "vi"
+            quit = ["ctrl-d"]
+            "#,
+        )
+        .expect("valid keys");
+        let keymap = Keymap::new(&config).expect("valid keymap");
+
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        assert_eq!(keymap.action(&key(KeyCode::Char('q'), KeyModifiers::NONE)), None);
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('j'), KeyModifiers::NONE)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keymap.action(&key(KeyCode::Char('R'), KeyModifiers::SHIFT)),
+            Some(Action::Retry)
+        );
+    }
+
+    #[test]
+    fn rejects_conflicts() {
+        let config: KeysConfig = toml::from_str(r#"run = ["d"]"#).expect("valid keys");
+        assert!(Keymap::new(&config).is_err());
+    }
+}
@@ -1,16 +1,28 @@
 #![feature(more_qualified_paths)]
-use std::env;
-use std::fs;
+// an explicit `return` marks what a function hands back
+#![allow(clippy::needless_return)]
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
 
 use clap::{Parser, Subcommand};
+use ratatui::crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 
+mod cache;
 mod cmds;
 mod theme;
 mod config;
 mod events;
 mod fetchers;
 mod gitlab_ref;
+mod humanize;
+mod keymap;
+mod prompt;
+mod recent;
+mod session;
+mod webhook;
+#[cfg(test)]
+mod testing;
 
 use crate::config::*;
 
@@ -18,6 +30,19 @@ use crate::config::*;
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Record every update the views receive into this file
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+    /// Replay a recorded session instead of contacting GitLab
+    #[arg(long, global = true, value_name = "FILE")]
+    replay: Option<PathBuf>,
+    /// Speed of the replay, 2 replays twice as fast as recorded
+    #[arg(long, global = true, default_value_t = 1.0)]
+    replay_speed: f64,
+    /// Config file to use instead of $GMON_CONFIG or the one in
+    /// $XDG_CONFIG_HOME/gmon (~/.config/gmon)
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
     #[clap(subcommand)]
     command: Command,
 }
@@ -26,55 +51,235 @@ struct Cli {
 enum Command {
     Pipelines(cmds::pipelines::PipelinesArgs),
     Pipeline(cmds::pipeline::PipelineArgs),
+    /// Pipeline statistics and flaky jobs of the last pipelines
+    Stats(cmds::stats::StatsArgs),
+    /// Last deployments of the environments of a project
+    Envs(cmds::envs::EnvsArgs),
+    /// Run a new pipeline and monitor it
+    Run(cmds::run::RunArgs),
+    /// Open a dashboard of the config or of the .gmon.toml of the current directory
+    Dash(cmds::dash::DashArgs),
+    /// Download the artifacts of a job of the latest successful pipeline
+    Artifacts(cmds::artifacts::ArtifactsArgs),
+    /// Create, show or check the config
+    Config(cmds::config::ConfigArgs),
+    /// Show the user and the scopes and expiry of the token
+    Whoami(cmds::whoami::WhoamiArgs),
+    /// Pick one of your projects and branches and monitor its pipelines
+    Pick(cmds::pick::PickArgs),
+    /// Print the latest pipeline of a ref as one line for status bars and prompts
+    Status(cmds::status::StatusArgs),
+    /// Receive the webhooks of GitLab and monitor pipelines without polling
+    Serve(cmds::serve::ServeArgs),
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
-    let config_file = env::var("HOME").expect("$HOME not set") + "/.config/gmon/config.toml";
-    let config_str = match fs::read_to_string(&config_file) {
-        Ok(s) => s,
-        Err(s) => {
-            eprintln!("failed reading config {} due to: {}", &config_file, &s);
+    // works without a (valid) config
+    if let Command::Config(args) = &cli.command {
+        return cmds::config::run(&config_file(cli.config.as_deref()), args).await;
+    }
+
+    // only these views pass their updates through the session
+    let replayable = matches!(cli.command, Command::Pipelines(_) | Command::Pipeline(_));
+    if let Some(path) = &cli.replay {
+        if !replayable {
+            eprintln!("only the pipelines and pipeline commands can be replayed");
             process::exit(2);
-        },
-    };
-    let config: Config = match toml::from_str(&config_str) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("failed loading config {} due to: {}", &config_file, e.message());
+        }
+        if cli.replay_speed <= 0.0 {
+            eprintln!("the replay speed has to be positive");
             process::exit(2);
-        },
-    };
-
-    let gapi = match gitlab::GitlabBuilder::new(config.host, config.token)
-        .cert_insecure()
-        .build_async()
-        .await {
+        }
+        match session::Session::replay(path, cli.replay_speed) {
+            Ok(s) => session::init(s),
+            Err(err) => {
+                eprintln!("failed reading session {} due to: {}", path.display(), err);
+                process::exit(2);
+            }
+        }
+    }
+    if let Some(path) = &cli.record {
+        if !replayable {
+            eprintln!("only the pipelines and pipeline commands can be recorded");
+            process::exit(2);
+        }
+        match session::Session::record(path) {
+            Ok(s) => session::init(s),
+            Err(err) => {
+                eprintln!("failed creating session {} due to: {}", path.display(), err);
+                process::exit(2);
+            }
+        }
+    }
 
-            Ok(api) => api,
+    let config = match session::is_replay() {
+        true => None,
+        false => Some(load_config(&config_file(cli.config.as_deref()))),
+    };
+    if let Some(config) = &config {
+        match keymap::Keymap::new(&config.keys) {
+            Ok(k) => keymap::init(k),
             Err(err) => {
-                eprintln!("connection to gitlab failed due to: {}", err);
-                process::exit(1);
-            },
+                eprintln!("invalid keys in the config: {}", err);
+                process::exit(2);
+            }
+        }
+    }
+    // connects only when its cache is outdated
+    if let (Command::Status(args), Some(config)) = (&cli.command, &config) {
+        return cmds::status::run(config, args).await;
+    }
+    let gapi = match &config {
+        Some(config) => Some(connect(config).await),
+        None => None,
+    };
+    // runs alongside the command instead of delaying its first frame
+    let check = match (&cli.command, &gapi) {
+        (Command::Whoami(args), Some(gapi)) => {
+            let host = &config.as_ref().expect("config is loaded").host;
+            return cmds::whoami::run(gapi.clone(), host, args).await;
+        }
+        (_, Some(gapi)) => Some(tokio::spawn(cmds::whoami::check(gapi.clone()))),
+        _ => None,
     };
 
+    // headless commands must not touch the terminal
+    match &cli.command {
+        Command::Stats(args) => cmds::stats::run(gapi.clone().expect("connected"), args).await,
+        Command::Artifacts(args) => {
+            cmds::artifacts::run(gapi.clone().expect("connected"), args).await
+        }
+        _ => {}
+    }
+    if matches!(cli.command, Command::Stats(_) | Command::Artifacts(_)) {
+        if let Some(failure) = report(check, true).await {
+            eprintln!("{}", failure.message);
+            process::exit(failure.code);
+        }
+        return;
+    }
+
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
+        _ = ratatui::crossterm::execute!(io::stdout(), DisableMouseCapture);
         _ = ratatui::crossterm::terminal::disable_raw_mode();
         ratatui::restore();
         original_hook(info);
     }));
+    if let Err(err) = ratatui::crossterm::execute!(io::stdout(), EnableMouseCapture) {
+        eprintln!("mouse support is not available: {}", err);
+    }
 
-    match &cli.command {
-        Command::Pipelines(args) => {
-            cmds::pipelines::run(gapi, args).await;
+    let result = match &cli.command {
+        Command::Pipelines(args) => cmds::pipelines::run(gapi, args).await,
+        Command::Pipeline(args) => cmds::pipeline::run(gapi, args).await,
+        Command::Run(args) => cmds::run::run(gapi.expect("connected"), args).await,
+        Command::Pick(args) => {
+            cmds::pick::run(gapi.expect("connected"), args).await;
+            Ok(())
         }
-        Command::Pipeline(args) => {
-            cmds::pipeline::run(gapi, args).await;
+        Command::Envs(args) => {
+            cmds::envs::run(gapi.expect("connected"), args).await;
+            Ok(())
         }
-    }
+        Command::Dash(args) => {
+            let config = config.expect("config is loaded");
+            cmds::dash::run(gapi.expect("connected"), config.dashboards, args).await
+        }
+        Command::Serve(args) => {
+            let config = config.expect("config is loaded");
+            cmds::serve::run(gapi, config.webhook_secret, args).await
+        }
+        Command::Stats(_)
+        | Command::Artifacts(_)
+        | Command::Config(_)
+        | Command::Whoami(_)
+        | Command::Status(_) => unreachable!("headless command"),
+    };
+    _ = ratatui::crossterm::execute!(io::stdout(), DisableMouseCapture);
     ratatui::restore();
     let _ = ratatui::crossterm::terminal::disable_raw_mode();
+    let result = match report(check, false).await {
+        Some(failure) if result.is_ok() => Err(failure),
+        _ => result,
+    };
+    if let Err(failure) = result {
+        eprintln!("{}", failure.message);
+        process::exit(failure.code);
+    }
+}
+
+/// Prints the warning of the token check and returns its failure. Unless
+/// `wait`ing for it, a check still running once the views are closed is of no
+/// interest anymore.
+async fn report(
+    check: Option<tokio::task::JoinHandle<Result<Option<String>, cmds::Failure>>>,
+    wait: bool,
+) -> Option<cmds::Failure> {
+    let check = check?;
+    if !wait && !check.is_finished() {
+        check.abort();
+        return None;
+    }
+    match check.await {
+        Ok(Ok(Some(warning))) => {
+            eprintln!("warning: {}", warning);
+            None
+        }
+        Ok(Ok(None)) | Err(_) => None,
+        Ok(Err(failure)) => Some(failure),
+    }
+}
+
+/// The config file to use, only resolved when it is read or written: a replay
+/// does without one.
+fn config_file(path: Option<&Path>) -> PathBuf {
+    match config_path(path) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    }
+}
+
+fn load_config(config_file: &Path) -> Config {
+    match Config::load(config_file) {
+        Ok(c) => c,
+        Err(err) => {
+            eprintln!("{}", err);
+            if !config_file.exists() {
+                eprintln!("create it with: gmon config init");
+            }
+            process::exit(2);
+        },
+    }
+}
+
+/// Connects to GitLab, which checks the token as well.
+pub(crate) async fn gitlab_api(
+    host: &str,
+    token: &str,
+) -> Result<gitlab::AsyncGitlab, gitlab::GitlabError> {
+    gitlab::GitlabBuilder::new(host, token)
+        .cert_insecure()
+        .build_async()
+        .await
+}
+
+async fn connect(config: &Config) -> gitlab::AsyncGitlab {
+    match gitlab_api(&config.host, &config.token).await {
+        Ok(api) => {
+            fetchers::rest::init(&config.token);
+            api
+        }
+        Err(err) => {
+            eprintln!("connection to gitlab failed due to: {}", err);
+            eprintln!("check host and token with: gmon config validate");
+            process::exit(1);
+        }
+    }
 }
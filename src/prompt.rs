@@ -0,0 +1,77 @@
+//! Single line text input a view shows in place of its key hints, e.g. to ask
+//! for a path. Keys reach it while [`crate::events::EventHandler::text_input`]
+//! is on.
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::prelude::*;
+
+use crate::theme;
+use crate::theme::Theme;
+
+pub struct Prompt {
+    label: String,
+    text: String,
+}
+
+/// What a key did to the prompt.
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Editing,
+    /// confirmed with enter
+    Done(String),
+    /// aborted with escape
+    Canceled,
+}
+
+impl Prompt {
+    /// A prompt asking for `label`, `text` is offered as the answer.
+    pub fn new(label: &str, text: String) -> Self {
+        Prompt {
+            label: label.to_string(),
+            text,
+        }
+    }
+
+    pub fn key(&mut self, key: &KeyEvent) -> Input {
+        match key.code {
+            KeyCode::Esc => return Input::Canceled,
+            KeyCode::Enter => return Input::Done(self.text.clone()),
+            KeyCode::Backspace => _ = self.text.pop(),
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.text.clear()
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.text.push(c),
+            _ => {}
+        }
+        Input::Editing
+    }
+
+    pub fn line(&self) -> Line<'static> {
+        Line::from(vec![
+            Span::styled(format!("{}: ", self.label), theme::Catpuccin::blue()),
+            Span::styled(self.text.clone(), theme::Catpuccin::text()),
+            Span::styled("█", theme::Catpuccin::text()),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_the_offered_text() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        let mut prompt = Prompt::new("save to", "junit.xml".to_string());
+
+        assert_eq!(prompt.key(&key(KeyCode::Backspace)), Input::Editing);
+        assert_eq!(prompt.key(&key(KeyCode::Char('s'))), Input::Editing);
+        assert_eq!(
+            prompt.key(&key(KeyCode::Enter)),
+            Input::Done("junit.xms".to_string())
+        );
+
+        prompt.key(&KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(prompt.key(&key(KeyCode::Enter)), Input::Done(String::new()));
+        assert_eq!(prompt.key(&key(KeyCode::Esc)), Input::Canceled);
+    }
+}
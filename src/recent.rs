@@ -0,0 +1,151 @@
+//! The refs watched lately, kept in `$XDG_STATE_HOME/gmon/recent.json` (or
+//! `~/.local/state/gmon/recent.json`) to offer them again later on.
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::gitlab_ref::GitlabRef;
+
+/// Number of refs remembered.
+const LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recent {
+    /// like `path/to/project@main`
+    #[serde(rename = "ref")]
+    pub gitlab_ref: String,
+    /// when the ref was watched last
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The recent refs, the most recent one first.
+pub fn load() -> Vec<Recent> {
+    path().map_or_else(Vec::new, |p| load_from(&p))
+}
+
+fn load_from(path: &Path) -> Vec<Recent> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&content).unwrap_or_default()
+}
+
+/// The ref watched last, for `gmon pipelines -`.
+pub fn last() -> Option<GitlabRef> {
+    path().and_then(|p| last_from(&p))
+}
+
+fn last_from(path: &Path) -> Option<GitlabRef> {
+    load_from(path)
+        .into_iter()
+        .next()
+        .map(|r| GitlabRef::from(r.gitlab_ref.as_str()))
+}
+
+/// The ref given to a command, `-` standing for the ref watched last, which is
+/// remembered as watched now. Every command taking a ref goes through here.
+pub fn watch(gitlab_ref: &GitlabRef) -> Result<GitlabRef, String> {
+    let gitlab_ref = resolve(gitlab_ref)?;
+    record(&gitlab_ref);
+    Ok(gitlab_ref)
+}
+
+/// `gitlab_ref` with `-` replaced by the ref watched last.
+pub fn resolve(gitlab_ref: &GitlabRef) -> Result<GitlabRef, String> {
+    match gitlab_ref {
+        GitlabRef::Repo(last) if last == "-" => self::last().ok_or("no ref was watched yet".into()),
+        gitlab_ref => Ok(gitlab_ref.clone()),
+    }
+}
+
+/// Remembers that `gitlab_ref` is watched now; failures are ignored as the
+/// history is only a convenience.
+pub fn record(gitlab_ref: &GitlabRef) {
+    if crate::session::is_replay() {
+        return;
+    }
+    if let Some(path) = path() {
+        record_in(&path, gitlab_ref);
+    }
+}
+
+fn record_in(path: &Path, gitlab_ref: &GitlabRef) {
+    let gitlab_ref = gitlab_ref.to_string();
+    let mut recent = load_from(path);
+    recent.retain(|r| r.gitlab_ref != gitlab_ref);
+    recent.insert(
+        0,
+        Recent {
+            gitlab_ref,
+            at: chrono::Utc::now(),
+        },
+    );
+    recent.truncate(LIMIT);
+
+    if let Some(dir) = path.parent() {
+        _ = fs::create_dir_all(dir);
+    }
+    if let Ok(content) = serde_json::to_string_pretty(&recent) {
+        _ = fs::write(path, content);
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let state = env::var_os("XDG_STATE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("state")))?;
+    Some(state.join("gmon").join("recent.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A file of its own per test, the tests run in parallel.
+    fn file(name: &str) -> PathBuf {
+        let path = env::temp_dir()
+            .join(format!("gmon-{}", std::process::id()))
+            .join(format!("{}.json", name));
+        _ = fs::remove_file(&path);
+        path
+    }
+
+    fn refs(path: &Path) -> Vec<String> {
+        load_from(path).into_iter().map(|r| r.gitlab_ref).collect()
+    }
+
+    #[test]
+    fn records_the_most_recent_first_once() {
+        let path = file("recent_order");
+        assert!(load_from(&path).is_empty());
+        assert!(last_from(&path).is_none());
+
+        record_in(&path, &GitlabRef::from("group/project@main"));
+        record_in(&path, &GitlabRef::from("group/docs"));
+        record_in(&path, &GitlabRef::from("group/project@main"));
+
+        assert_eq!(refs(&path), ["group/project@main", "group/docs"]);
+        assert_eq!(
+            last_from(&path).map(|r| r.to_string()).as_deref(),
+            Some("group/project@main")
+        );
+        _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn keeps_the_latest_refs_up_to_the_limit() {
+        let path = file("recent_limit");
+        for i in 0..LIMIT + 5 {
+            let gitlab_ref = format!("group/project{}", i);
+            record_in(&path, &GitlabRef::from(gitlab_ref.as_str()));
+        }
+
+        let refs = refs(&path);
+        assert_eq!(refs.len(), LIMIT);
+        assert_eq!(refs[0], format!("group/project{}", LIMIT + 4));
+        assert_eq!(refs[LIMIT - 1], "group/project5");
+        _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,211 @@
+//! Recording of the updates the views receive and replaying them later on
+//! without contacting GitLab, e.g. for demos or bug reports.
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{channel, Receiver};
+
+static SESSION: OnceLock<Session> = OnceLock::new();
+
+pub enum Session {
+    Live,
+    Record {
+        file: Arc<Mutex<BufWriter<File>>>,
+        start: Instant,
+    },
+    Replay {
+        entries: Arc<Vec<Entry>>,
+        /// 2.0 replays twice as fast as recorded
+        speed: f64,
+    },
+}
+
+/// One line of a recorded session.
+#[derive(Serialize, Deserialize)]
+pub struct Entry {
+    /// milliseconds since the start of the recording
+    at: u64,
+    /// which fetcher sent the update like `pipelines`
+    kind: String,
+    /// the params the fetcher was started with
+    params: serde_json::Value,
+    update: serde_json::Value,
+}
+
+impl Session {
+    pub fn record(path: &Path) -> io::Result<Self> {
+        Ok(Session::Record {
+            file: Arc::new(Mutex::new(BufWriter::new(File::create(path)?))),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn replay(path: &Path, speed: f64) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = serde_json::from_str(&line)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            entries.push(entry);
+        }
+
+        Ok(Session::Replay {
+            entries: Arc::new(entries),
+            speed,
+        })
+    }
+}
+
+/// Sets the session of this process, must be called at most once.
+pub fn init(session: Session) {
+    if SESSION.set(session).is_err() {
+        panic!("session initialized twice");
+    }
+}
+
+fn current() -> &'static Session {
+    SESSION.get_or_init(|| Session::Live)
+}
+
+pub fn is_replay() -> bool {
+    matches!(current(), Session::Replay { .. })
+}
+
+/// The updates of the fetcher started by `fetch`. While recording they are
+/// written to the session as they pass through; when replaying `fetch` is not
+/// called at all and the updates recorded for the same `kind` and `params` are
+/// sent instead, keeping their original intervals and stamped as fetched just
+/// now.
+pub fn updates<P, T>(
+    kind: &'static str,
+    params: &P,
+    fetch: impl FnOnce() -> Receiver<T>,
+) -> Receiver<T>
+where
+    P: Serialize,
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    let params = serde_json::to_value(params).expect("params are serializable");
+
+    match current() {
+        Session::Live => fetch(),
+        Session::Record { file, start } => {
+            let mut fetched = fetch();
+            let (sender, receiver) = channel(1);
+            let file = file.clone();
+            let start = *start;
+
+            tokio::spawn(async move {
+                while let Some(update) = fetched.recv().await {
+                    let entry = Entry {
+                        at: start.elapsed().as_millis() as u64,
+                        kind: kind.to_string(),
+                        params: params.clone(),
+                        update: serde_json::to_value(&update).expect("update is serializable"),
+                    };
+                    {
+                        let mut file = file.lock().expect("session file lock");
+                        // a broken recording must not break the view
+                        _ = serde_json::to_writer(&mut *file, &entry);
+                        _ = writeln!(file);
+                        _ = file.flush();
+                    }
+                    if sender.send(update).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            receiver
+        }
+        Session::Replay { entries, speed } => {
+            let (sender, receiver) = channel(1);
+            let entries = entries.clone();
+            let speed = *speed;
+
+            tokio::spawn(async move {
+                // collected up front, a lazy filter held across the awaits
+                // makes the task not `Send`
+                let recorded: Vec<&Entry> = entries
+                    .iter()
+                    .filter(|e| e.kind == kind && e.params == params)
+                    .collect();
+                let Some(first) = recorded.first().map(|e| e.at) else {
+                    return;
+                };
+                let start = Instant::now();
+
+                for entry in recorded {
+                    let offset =
+                        Duration::from_millis(entry.at.saturating_sub(first)).div_f64(speed);
+                    tokio::time::sleep_until((start + offset).into()).await;
+
+                    let mut update = entry.update.clone();
+                    restamp(&mut update, &now());
+                    let Ok(update) = serde_json::from_value::<T>(update) else {
+                        continue;
+                    };
+                    if sender.send(update).await.is_err() {
+                        break;
+                    }
+                }
+            });
+
+            receiver
+        }
+    }
+}
+
+fn now() -> serde_json::Value {
+    serde_json::to_value(chrono::Utc::now()).expect("time is serializable")
+}
+
+/// Replaces every `fetched_at` within `update` by `at`, the views would show
+/// the age of the recording otherwise.
+fn restamp(update: &mut serde_json::Value, at: &serde_json::Value) {
+    match update {
+        serde_json::Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                match name.as_str() {
+                    "fetched_at" => *value = at.clone(),
+                    _ => restamp(value, at),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                restamp(value, at);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn restamps_nested_updates() {
+        let mut update = json!([
+            {"Ok": {"project": "group/project", "fetched_at": "2024-01-01T00:00:00Z"}},
+            {"Err": {"Transient": "failed"}},
+        ]);
+        restamp(&mut update, &json!("2025-01-01T00:00:00Z"));
+        assert_eq!(
+            update,
+            json!([
+                {"Ok": {"project": "group/project", "fetched_at": "2025-01-01T00:00:00Z"}},
+                {"Err": {"Transient": "failed"}},
+            ])
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Helpers for the tests: recorded GitLab responses and snapshots of rendered views.
+use graphql_client::{GraphQLQuery, QueryBody};
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::Terminal;
+use serde::Deserialize;
+use std::env;
+use std::fmt::Debug;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::fetchers::source::Source;
+
+/// The recorded responses in `fixtures/`.
+pub fn fixtures() -> Fixtures {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+    Fixtures::load(&dir).expect("fixtures to be readable")
+}
+
+pub fn terminal(width: u16, height: u16) -> Terminal<TestBackend> {
+    Terminal::new(TestBackend::new(width, height)).expect("test terminal")
+}
+
+/// Compares the symbols of `buffer` against `snapshots/<name>.txt`.
+///
+/// Set `UPDATE_SNAPSHOTS=1` to record new snapshots or to re-record all of
+/// them after an intended change of the rendering; otherwise a missing
+/// snapshot fails like a changed one.
+pub fn assert_snapshot(name: &str, buffer: &Buffer) {
+    let rendered = buffer_lines(buffer);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{}.txt", name));
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().expect("snapshot dir")).expect("snapshot dir");
+        fs::write(&path, &rendered).expect("snapshot to be writable");
+        return;
+    }
+
+    let Ok(expected) = fs::read_to_string(&path) else {
+        panic!(
+            "snapshot {} is missing, record it with UPDATE_SNAPSHOTS=1\n--- rendered\n{}",
+            path.display(),
+            rendered
+        );
+    };
+    assert!(
+        expected == rendered,
+        "snapshot {} changed\n--- expected\n{}\n--- rendered\n{}",
+        path.display(),
+        expected,
+        rendered
+    );
+}
+
+fn buffer_lines(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut lines = String::new();
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            lines.push_str(buffer[(x, y)].symbol());
+        }
+        lines.push('\n');
+    }
+    lines
+}
+
+/// A recorded response of a GraphQL query.
+#[derive(Debug, Deserialize)]
+pub struct Fixture {
+    /// name of the query like `JobsQuery`
+    pub operation: String,
+    /// the fixture only answers queries whose variables contain all of these;
+    /// without variables it answers every query of the operation
+    #[serde(default)]
+    pub variables: serde_json::Map<String, serde_json::Value>,
+    /// the `data` of the response
+    #[serde(default)]
+    pub response: serde_json::Value,
+    /// answers with these GraphQL errors instead of the response
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+/// Answers queries with recorded responses instead of asking GitLab.
+#[derive(Debug, Clone, Default)]
+pub struct Fixtures {
+    fixtures: Arc<Vec<Fixture>>,
+}
+
+impl Fixtures {
+    /// Reads every `*.json` file of `dir`, each of them holding one [`Fixture`].
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let mut fixtures = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let fixture: Fixture = serde_json::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            fixtures.push(fixture);
+        }
+        // most specific fixtures first
+        fixtures.sort_by_key(|f| std::cmp::Reverse(f.variables.len()));
+
+        Ok(Fixtures {
+            fixtures: Arc::new(fixtures),
+        })
+    }
+
+    fn find(&self, operation: &str, variables: &serde_json::Value) -> Option<&Fixture> {
+        self.fixtures
+            .iter()
+            .filter(|f| f.operation == operation)
+            .find(|f| {
+                f.variables
+                    .iter()
+                    .all(|(name, value)| variables.get(name) == Some(value))
+            })
+    }
+}
+
+impl Source for Fixtures {
+    fn query<Q>(
+        &self,
+        query: &QueryBody<Q::Variables>,
+    ) -> impl Future<Output = Result<Q::ResponseData, gitlab::GitlabError>> + Send
+    where
+        Q: GraphQLQuery,
+        Q::Variables: Debug + Sync,
+        Q::ResponseData: Clone + Send + Sync + 'static,
+    {
+        let variables = serde_json::to_value(&query.variables).expect("variables are serializable");
+        let fixture = self
+            .find(query.operation_name, &variables)
+            .unwrap_or_else(|| {
+                panic!("no fixture for {} with {}", query.operation_name, variables)
+            });
+        if !fixture.errors.is_empty() {
+            let message = fixture
+                .errors
+                .iter()
+                .map(|message| graphql_client::Error {
+                    message: message.clone(),
+                    locations: None,
+                    path: None,
+                    extensions: None,
+                })
+                .collect();
+            return std::future::ready(Err(gitlab::GitlabError::GraphQL { message }));
+        }
+        let response = fixture.response.clone();
+        let data = serde_json::from_value::<Q::ResponseData>(response).unwrap_or_else(|e| {
+            panic!("fixture for {} does not match the query: {}", query.operation_name, e)
+        });
+
+        std::future::ready(Ok(data))
+    }
+}
@@ -9,13 +9,20 @@ pub trait Theme {
     fn green() -> Style;
     fn blue() -> Style;
 
-    fn block(&self) -> Block {
+    fn block(&self) -> Block<'_> {
         Block::bordered()
             .border_type(BorderType::Thick)
             .border_style(Self::text())
             .style(Self::background())
             .title_style(Self::text())
     }
+
+    /// Placeholder of a view until its first update arrives.
+    fn loading(&self, title: String) -> Block<'_> {
+        self.block()
+            .title(title)
+            .title_bottom(Line::from("loading ...").right_aligned())
+    }
 }
 
 pub struct Catpuccin;
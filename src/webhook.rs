@@ -0,0 +1,269 @@
+//! Receiver of the Pipeline and Job webhooks of GitLab. While it runs, the
+//! fetchers poll once an event of their project arrives instead of after
+//! every interval, see [`crate::fetchers::poll::Schedule::watch`].
+use serde::Deserialize;
+use std::io;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+use crate::fetchers::poll::Kind;
+
+static PUSHES: OnceLock<broadcast::Sender<Push>> = OnceLock::new();
+
+/// Longest accepted request line or header.
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+/// Pipeline events of large pipelines list all of their jobs.
+const MAX_BODY: usize = 10 * 1024 * 1024;
+/// Time a client gets to send its request.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A pipeline or one of its jobs of `project` changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Push {
+    pub project: String,
+    pub kind: Kind,
+}
+
+/// The part of the payloads both events share.
+#[derive(Deserialize)]
+struct Payload {
+    object_kind: String,
+    project: Project,
+}
+
+#[derive(Deserialize)]
+struct Project {
+    path_with_namespace: String,
+}
+
+struct Request {
+    method: String,
+    /// names in lower case
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The events received so far; `None` unless the receiver runs.
+pub fn subscribe() -> Option<broadcast::Receiver<Push>> {
+    PUSHES.get().map(|sender| sender.subscribe())
+}
+
+/// Accepts webhooks on `listener` in the background, only the ones sending
+/// `secret` in their `X-Gitlab-Token` header are passed on.
+pub fn serve(listener: TcpListener, secret: String) {
+    let sender = PUSHES.get_or_init(|| broadcast::channel(64).0).clone();
+
+    tokio::spawn(async move {
+        loop {
+            // e.g. too many open files, the next client may be luckier
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let secret = secret.clone();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                // a client that is gone or too slow does not get an answer
+                _ = tokio::time::timeout(TIMEOUT, handle(stream, &secret, &sender)).await;
+            });
+        }
+    });
+}
+
+/// Answers a single request, the connection is closed afterwards.
+async fn handle<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    secret: &str,
+    sender: &broadcast::Sender<Push>,
+) -> io::Result<()> {
+    let status = match read_request(&mut stream).await {
+        Ok(request) => {
+            let (status, push) = respond(&request, secret);
+            if let Some(push) = push {
+                // nobody listens before the first view is open
+                _ = sender.send(push);
+            }
+            status
+        }
+        Err(_) => "400 Bad Request",
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+        status
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn read_request<R: AsyncRead + Unpin>(stream: R) -> io::Result<Request> {
+    let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+    let mut reader = BufReader::new(stream);
+
+    let request_line = read_line(&mut reader).await?;
+    let method = request_line
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| invalid("empty request"))?
+        .to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(&mut reader).await?;
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid("malformed header"))?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+    }
+
+    let mut request = Request {
+        method,
+        headers,
+        body: Vec::new(),
+    };
+    let length = match request.header("content-length") {
+        Some(length) => length
+            .parse()
+            .map_err(|_| invalid("malformed content length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(invalid("body too large"));
+    }
+    request.body = vec![0; length];
+    reader.read_exact(&mut request.body).await?;
+    Ok(request)
+}
+
+/// One line without its line break.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    let read = (&mut *reader).take(MAX_LINE).read_line(&mut line).await?;
+    if read == 0 || !line.ends_with('\n') {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// The status to answer with and the event to pass on, if any.
+fn respond(request: &Request, secret: &str) -> (&'static str, Option<Push>) {
+    if request.method != "POST" {
+        return ("405 Method Not Allowed", None);
+    }
+    let token = request.header("x-gitlab-token").unwrap_or_default();
+    if !same(token, secret) {
+        return ("401 Unauthorized", None);
+    }
+    let Ok(payload) = serde_json::from_slice::<Payload>(&request.body) else {
+        return ("400 Bad Request", None);
+    };
+    let kind = match payload.object_kind.as_str() {
+        "pipeline" => Kind::Pipeline,
+        "build" => Kind::Job,
+        // other events of the same webhook are of no interest
+        _ => return ("200 OK", None),
+    };
+    let push = Push {
+        project: payload.project.path_with_namespace,
+        kind,
+    };
+    ("200 OK", Some(push))
+}
+
+/// Compares in the same time no matter where the strings differ, so the
+/// secret can not be guessed byte by byte.
+fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(token: &str, body: &str) -> Vec<u8> {
+        format!(
+            "POST /hooks HTTP/1.1\r\nHost: localhost\r\nX-Gitlab-Event: Pipeline Hook\r\n\
+             X-Gitlab-Token: {}\r\nContent-Length: {}\r\n\r\n{}",
+            token,
+            body.len(),
+            body
+        )
+        .into_bytes()
+    }
+
+    async fn post(request: &[u8]) -> (String, Option<Push>) {
+        let (sender, mut pushes) = broadcast::channel(8);
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        client.write_all(request).await.expect("request is written");
+        handle(server, "s3cret", &sender)
+            .await
+            .expect("request is answered");
+
+        let mut response = String::new();
+        client
+            .read_to_string(&mut response)
+            .await
+            .expect("response is read");
+        (response, pushes.try_recv().ok())
+    }
+
+    #[tokio::test]
+    async fn passes_on_events() {
+        let pipeline = include_str!("../fixtures/webhooks/pipeline.json");
+        let (response, push) = post(&request("s3cret", pipeline)).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert_eq!(
+            push,
+            Some(Push {
+                project: "group/project".to_string(),
+                kind: Kind::Pipeline,
+            })
+        );
+
+        let job = include_str!("../fixtures/webhooks/job.json");
+        let (_, push) = post(&request("s3cret", job)).await;
+        assert_eq!(push.map(|p| p.kind), Some(Kind::Job));
+
+        let push_event = r#"{"object_kind":"push","project":{"path_with_namespace":"a/b"}}"#;
+        let (response, push) = post(&request("s3cret", push_event)).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(push.is_none());
+
+        let (response, _) = post(&request("s3cret", "not json")).await;
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[tokio::test]
+    async fn rejects_wrong_secret() {
+        let pipeline = include_str!("../fixtures/webhooks/pipeline.json");
+        let (response, push) = post(&request("guess", pipeline)).await;
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(push.is_none());
+
+        let (response, _) = post(b"GET /hooks HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+}